@@ -2,12 +2,31 @@ use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 use solana_program::pubkey::Pubkey;
+use spl_governance::state::enums::ProposalState;
 
-use crate::{processor::MintType, Vote};
+use crate::{
+    output::OutputFormat,
+    processor::{parse_age, parse_proposal_state, BufferSort, ChoiceType, MintType},
+    Vote,
+};
 
 #[derive(Parser)]
 #[clap(author, version, about)]
 pub struct Args {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    /// Output format of the command result: text, json or yaml.
+    #[arg(long, global = true, default_value = "text")]
+    pub output: OutputFormat,
+
+    #[clap(subcommand)]
+    pub command: Commands,
+}
+
+/// Flags every command accepts, selecting the cluster, signer and governance to act on.
+#[derive(clap::Args, Clone, Debug, Default)]
+pub struct GlobalArgs {
     /// Path to the keypair file.
     #[arg(short, long, global = true)]
     pub keypair_path: Option<PathBuf>,
@@ -16,17 +35,42 @@ pub struct Args {
     #[arg(short, long, global = true)]
     pub rpc_url: Option<String>,
 
-    #[clap(subcommand)]
-    pub command: Commands,
+    /// Simulate transactions and print what they would do instead of sending them.
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Named profile from ~/.config/themis/config.yml or themis.toml
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Realm address, overrides REALM_ID and the profile
+    #[arg(long, global = true)]
+    pub realm: Option<Pubkey>,
+
+    /// Governance address, overrides GOVERNANCE_ID and the profile
+    #[arg(long, global = true)]
+    pub governance: Option<Pubkey>,
 }
 
 #[derive(Clone, Subcommand)]
 pub enum Commands {
     /// Create a proposal for upgrading a program
     UpgradeProgram {
-        /// Proposal name
-        #[arg(short = 'b', long)]
-        source_buffer: Pubkey,
+        /// Program to upgrade, overrides PROGRAM_ID and the profile
+        #[arg(long)]
+        program_id: Option<Pubkey>,
+
+        /// Buffer holding the new program data
+        #[arg(short = 'b', long, required_unless_present = "manifest")]
+        source_buffer: Option<Pubkey>,
+
+        /// JSON or YAML file listing the programs to upgrade together, instead of one program
+        #[arg(long, conflicts_with_all = ["program_id", "source_buffer", "expected_hash"])]
+        manifest: Option<PathBuf>,
+
+        /// Give each program of the manifest its own option so they can pass independently
+        #[arg(long, requires = "manifest")]
+        option_per_program: bool,
 
         /// Account to return buffer funds to, defaults to authority keypair
         #[arg(short, long)]
@@ -37,21 +81,113 @@ pub enum Commands {
         name: String,
 
         /// Proposal description or link to proposal description
+        #[arg(short, long, required_unless_present = "auto_description")]
+        description: Option<String>,
+
+        /// Generate the description from the program and buffer: hashes, sizes, commit and link
+        #[arg(long, conflicts_with = "description")]
+        auto_description: bool,
+
+        /// Git commit of the new program for the generated description, defaults to GIT_COMMIT
+        #[arg(long)]
+        git_commit: Option<String>,
+
+        /// Link for the generated description, with {commit} and {program_id} placeholders
+        #[arg(long)]
+        link_template: Option<String>,
+
+        /// Mint type: Member or Council, defaults to the profile's or Council
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
+
         #[arg(short, long)]
-        description: String,
+        options: Vec<String>,
+
+        /// Vote type: Single or Multi, defaults to Multi when several options are given
+        #[arg(long)]
+        choice_type: Option<ChoiceType>,
+
+        /// Continue creating a proposal that was interrupted part way through
+        #[arg(long)]
+        resume: Option<Pubkey>,
 
-        /// Mint type: Member or Council
-        #[arg(short, long, default_value = "council")]
-        mint_type: MintType,
+        /// Fail instead of extending the ProgramData account in the same proposal when the
+        /// buffer doesn't fit
+        #[arg(long)]
+        no_extend: bool,
 
+        /// Refuse to propose the buffer unless its executable has this SHA-256 hash
+        #[arg(long)]
+        expected_hash: Option<String>,
+    },
+    /// Propose growing a program's ProgramData account so a bigger build fits
+    ExtendProgram {
+        /// Program to extend, overrides PROGRAM_ID and the profile
+        #[arg(long)]
+        program_id: Option<Pubkey>,
+
+        /// Number of bytes to add
+        #[arg(long)]
+        bytes: usize,
+
+        /// Proposal name, generated if omitted
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Proposal description, generated if omitted
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Mint type: Member or Council, defaults to the profile's or Council
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
+    },
+    /// Create a proposal from a file of arbitrary instructions
+    Propose {
+        /// Path to a JSON or YAML file describing the instructions to insert
+        instructions_file: PathBuf,
+
+        /// Add the transactions to this existing draft proposal instead of creating a new one
+        #[arg(short, long)]
+        proposal_id: Option<Pubkey>,
+
+        /// Proposal name
+        #[arg(short, long, required_unless_present_any = ["proposal_id", "resume"])]
+        name: Option<String>,
+
+        /// Proposal description or link to proposal description
+        #[arg(short, long, required_unless_present_any = ["proposal_id", "resume"])]
+        description: Option<String>,
+
+        /// Mint type: Member or Council, defaults to the profile's or Council
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
+
+        /// Option labels, defaults to the labels in the instructions file
         #[arg(short, long)]
         options: Vec<String>,
+
+        /// Vote type: Single or Multi, defaults to Multi when several options are given
+        #[arg(long)]
+        choice_type: Option<ChoiceType>,
+
+        /// Continue creating a proposal that was interrupted part way through
+        #[arg(long)]
+        resume: Option<Pubkey>,
+
+        /// Leave the proposal in Draft so more transactions can be added before signing off
+        #[arg(long)]
+        draft: bool,
     },
     /// Vote on a proposal
     Vote {
         /// Vote: true = yes, false = no
         vote_choice: Vote,
 
+        /// Indexes of the options to approve on a proposal with several options
+        #[arg(short, long, value_delimiter = ',')]
+        approve_options: Vec<u8>,
+
         /// Proposal pubkey
         #[arg(short, long)]
         proposal_id: Option<Pubkey>,
@@ -60,8 +196,13 @@ pub enum Commands {
         #[arg(short, long)]
         latest: bool,
 
-        #[arg(short, long, default_value = "council")]
-        mint_type: MintType,
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
+    },
+    /// Inspect proposals
+    Proposal {
+        #[clap(subcommand)]
+        command: ProposalCommands,
     },
     /// Execute a proposal
     Execute {
@@ -73,8 +214,8 @@ pub enum Commands {
         #[arg(short, long)]
         latest: bool,
 
-        #[arg(short, long, default_value = "council")]
-        mint_type: MintType,
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
     },
     /// Cancel a proposal
     Cancel {
@@ -86,27 +227,27 @@ pub enum Commands {
         #[arg(short, long)]
         latest: bool,
 
-        #[arg(short, long, default_value = "council")]
-        mint_type: MintType,
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
     },
     /// Deposit governance tokens
     Deposit {
         /// Amount of governance tokens to deposit
         amount: u64,
 
-        #[arg(short, long, default_value = "council")]
-        mint_type: MintType,
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
     },
     /// Withdraw governance tokens
     Withdraw {
-        #[arg(short, long, default_value = "council")]
-        mint_type: MintType,
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
     },
     /// Update a governance configuration
     UpdateConfig {
-        /// Mint type: Member or Council
-        #[arg(short, long, default_value = "council")]
-        mint_type: MintType,
+        /// Mint type: Member or Council, defaults to the profile's or Council
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
 
         #[arg(long)]
         vote_threshold_percentage: Option<u8>,
@@ -129,8 +270,23 @@ pub enum Commands {
     },
     /// Get a governance configuration
     GetGovConfig,
-    /// Get all buffers owned by the governance program
-    GetBuffers { authority: Pubkey },
+    /// List the buffers of an authority with their size, rent and age
+    GetBuffers {
+        authority: Pubkey,
+
+        /// Only list buffers last written at least this long ago, e.g. 30d or 12h
+        #[arg(long, value_parser = parse_age)]
+        min_age: Option<i64>,
+
+        /// Only list buffers of at least this many bytes
+        #[arg(long)]
+        min_size: Option<usize>,
+
+        /// Sort by age, size or lamports, oldest or largest first
+        #[arg(long, default_value = "age")]
+        sort: BufferSort,
+    },
+    /// Close buffers, directly when the keypair is their authority or through a proposal
     CloseBuffers {
         authority: Pubkey,
 
@@ -140,19 +296,186 @@ pub enum Commands {
         #[arg(short, long)]
         spill_account: Option<Pubkey>,
 
-        /// Proposal name
+        /// Proposal name, required unless the local keypair is the authority
         #[arg(short, long)]
-        name: String,
+        name: Option<String>,
 
         /// Proposal description or link to proposal description
         #[arg(short, long)]
-        description: String,
+        description: Option<String>,
 
-        /// Mint type: Member or Council
-        #[arg(short, long, default_value = "council")]
-        mint_type: MintType,
+        /// Mint type: Member or Council, defaults to the profile's or Council
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
 
         #[arg(short, long)]
         options: Vec<String>,
+
+        /// Vote type: Single or Multi, defaults to Multi when several options are given
+        #[arg(long)]
+        choice_type: Option<ChoiceType>,
+
+        /// Continue creating a proposal that was interrupted part way through
+        #[arg(long)]
+        resume: Option<Pubkey>,
+
+        /// Also close the buffers of the local keypair directly, without a proposal
+        #[arg(long)]
+        include_own: bool,
+    },
+    /// Upload and inspect program buffers
+    Buffer {
+        #[clap(subcommand)]
+        command: BufferCommands,
+    },
+    /// Inspect deployed programs and hand them over to the governance
+    #[clap(visible_alias = "programs")]
+    Program {
+        #[clap(subcommand)]
+        command: ProgramCommands,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+pub enum ProposalCommands {
+    /// Show a proposal's state, votes and transactions
+    Show {
+        /// Proposal pubkey
+        proposal_id: Option<Pubkey>,
+
+        /// Show the most recent proposal
+        #[arg(short, long)]
+        latest: bool,
+
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
+    },
+    /// List the governance's proposals
+    List {
+        /// Only list proposals in these states, e.g. Voting or Succeeded
+        #[arg(short, long, value_delimiter = ',', value_parser = parse_proposal_state)]
+        state: Vec<ProposalState>,
+
+        /// Only list proposals for this mint type: Member or Council
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+pub enum BufferCommands {
+    /// Write a program to a new buffer and hand its authority to the governance
+    Write {
+        /// Path to the program's .so file
+        program_path: PathBuf,
+
+        /// Continue writing to a buffer that was interrupted part way through
+        #[arg(long)]
+        resume: Option<Pubkey>,
+    },
+    /// Print the SHA-256 hash of the executable in a buffer, as solana-verify does
+    Hash {
+        /// Buffer address
+        buffer: Pubkey,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+pub enum ProgramCommands {
+    /// List the programs whose upgrade authority is the governance
+    List,
+    /// Print the SHA-256 hash of a deployed program's executable, as solana-verify does
+    Hash {
+        /// Program id
+        program_id: Pubkey,
+    },
+    /// Hand the upgrade authority of a program held by the local keypair to the governance
+    TransferAuthority {
+        /// Program to transfer, overrides PROGRAM_ID and the profile
+        #[arg(long)]
+        program_id: Option<Pubkey>,
+
+        /// Create a program governance for the program in the realm and make it the authority,
+        /// using the current governance's config
+        #[arg(long)]
+        create_governance: bool,
+
+        /// Mint of the keypair's token owner record creating the governance, defaults to the
+        /// profile's or Council
+        #[arg(short, long, requires = "create_governance")]
+        mint_type: Option<MintType>,
+    },
+    /// Propose moving a governed program's upgrade authority to another governance or multisig
+    SetAuthority {
+        /// New upgrade authority
+        new_authority: Pubkey,
+
+        /// Program to transfer, overrides PROGRAM_ID and the profile
+        #[arg(long)]
+        program_id: Option<Pubkey>,
+
+        /// Proposal name, generated if omitted
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Proposal description, generated if omitted
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Mint type: Member or Council, defaults to the profile's or Council
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Propose removing a governed program's upgrade authority, making it immutable
+    MakeImmutable {
+        /// Program to make immutable, overrides PROGRAM_ID and the profile
+        #[arg(long)]
+        program_id: Option<Pubkey>,
+
+        /// Proposal name, generated if omitted
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Proposal description, generated if omitted
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Mint type: Member or Council, defaults to the profile's or Council
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Propose closing a governed program to reclaim its ProgramData rent
+    Close {
+        /// Program to close, overrides PROGRAM_ID and the profile
+        #[arg(long)]
+        program_id: Option<Pubkey>,
+
+        /// Account receiving the rent, defaults to the governance's native treasury
+        #[arg(long)]
+        recipient: Option<Pubkey>,
+
+        /// Proposal name, generated if omitted
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Proposal description, generated if omitted
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Mint type: Member or Council, defaults to the profile's or Council
+        #[arg(short, long)]
+        mint_type: Option<MintType>,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
     },
 }
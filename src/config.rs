@@ -9,7 +9,18 @@ use solana_sdk::{
     hash::Hash,
     signature::{read_keypair_file, Keypair},
 };
-use std::{env, fs::File, path::PathBuf, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{args::GlobalArgs, processor::MintType};
+
+/// Name of the repo-local config file, looked up from the current directory upwards.
+const LOCAL_CONFIG_FILE: &str = "themis.toml";
 
 #[derive(Debug, Deserialize, Serialize)]
 struct SolanaConfig {
@@ -18,6 +29,43 @@ struct SolanaConfig {
     pub commitment: String,
 }
 
+/// Themis settings read from `~/.config/themis/config.yml` and a repo-local `themis.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemisConfig {
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// A named set of defaults for one realm and governance, and optionally the program it governs.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub rpc_url: Option<String>,
+    pub keypair_path: Option<PathBuf>,
+    pub realm: Option<String>,
+    pub governance: Option<String>,
+    pub program_id: Option<String>,
+    pub mint_type: Option<String>,
+    pub link_template: Option<String>,
+}
+
+impl Profile {
+    /// Fields set in `other` take precedence over the ones in `self`.
+    fn merge(self, other: Profile) -> Profile {
+        Profile {
+            rpc_url: other.rpc_url.or(self.rpc_url),
+            keypair_path: other.keypair_path.or(self.keypair_path),
+            realm: other.realm.or(self.realm),
+            governance: other.governance.or(self.governance),
+            program_id: other.program_id.or(self.program_id),
+            mint_type: other.mint_type.or(self.mint_type),
+            link_template: other.link_template.or(self.link_template),
+        }
+    }
+}
+
 pub struct CliConfig {
     pub client: RpcClient,
     pub keypair: Keypair,
@@ -25,6 +73,10 @@ pub struct CliConfig {
     pub recent_slot: Slot,
     pub realm_id: Pubkey,
     pub governance_id: Pubkey,
+    pub program_id: Option<Pubkey>,
+    pub default_mint_type: MintType,
+    pub link_template: Option<String>,
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Default)]
@@ -32,6 +84,12 @@ pub struct CliConfigBuilder {
     pub json_rpc_url: Option<String>,
     pub keypair_path: Option<PathBuf>,
     pub commitment: Option<String>,
+    pub realm_id: Option<Pubkey>,
+    pub governance_id: Option<Pubkey>,
+    pub program_id: Option<Pubkey>,
+    pub default_mint_type: Option<MintType>,
+    pub link_template: Option<String>,
+    pub dry_run: bool,
 }
 
 impl CliConfigBuilder {
@@ -40,6 +98,12 @@ impl CliConfigBuilder {
             json_rpc_url: None,
             keypair_path: None,
             commitment: None,
+            realm_id: None,
+            governance_id: None,
+            program_id: None,
+            default_mint_type: None,
+            link_template: None,
+            dry_run: false,
         }
     }
     pub fn rpc_url(mut self, json_rpc_url: String) -> Self {
@@ -54,6 +118,108 @@ impl CliConfigBuilder {
         self.commitment = Some(commitment);
         self
     }
+    pub fn realm_id(mut self, realm_id: Pubkey) -> Self {
+        self.realm_id = Some(realm_id);
+        self
+    }
+    pub fn governance_id(mut self, governance_id: Pubkey) -> Self {
+        self.governance_id = Some(governance_id);
+        self
+    }
+    pub fn program_id(mut self, program_id: Pubkey) -> Self {
+        self.program_id = Some(program_id);
+        self
+    }
+    pub fn default_mint_type(mut self, mint_type: MintType) -> Self {
+        self.default_mint_type = Some(mint_type);
+        self
+    }
+    pub fn link_template(mut self, link_template: String) -> Self {
+        self.link_template = Some(link_template);
+        self
+    }
+    /// Applies the values set in a profile, overriding earlier ones.
+    pub fn profile(mut self, profile: Profile) -> Result<Self> {
+        if let Some(rpc_url) = profile.rpc_url {
+            self = self.rpc_url(rpc_url);
+        }
+        if let Some(keypair_path) = profile.keypair_path {
+            self = self.keypair_path(keypair_path);
+        }
+        if let Some(realm) = profile.realm {
+            self = self.realm_id(parse_pubkey("realm", &realm)?);
+        }
+        if let Some(governance) = profile.governance {
+            self = self.governance_id(parse_pubkey("governance", &governance)?);
+        }
+        if let Some(program_id) = profile.program_id {
+            self = self.program_id(parse_pubkey("program_id", &program_id)?);
+        }
+        if let Some(mint_type) = profile.mint_type {
+            self = self.default_mint_type(MintType::from_str(&mint_type)?);
+        }
+        if let Some(link_template) = profile.link_template {
+            self = self.link_template(link_template);
+        }
+        Ok(self)
+    }
+    /// Applies the `REALM_ID`, `GOVERNANCE_ID` and `PROGRAM_ID` env vars.
+    pub fn env_vars(mut self) -> Result<Self> {
+        if let Ok(realm) = env::var("REALM_ID") {
+            self = self.realm_id(parse_pubkey("REALM_ID", &realm)?);
+        }
+        if let Ok(governance) = env::var("GOVERNANCE_ID") {
+            self = self.governance_id(parse_pubkey("GOVERNANCE_ID", &governance)?);
+        }
+        if let Ok(program_id) = env::var("PROGRAM_ID") {
+            self = self.program_id(parse_pubkey("PROGRAM_ID", &program_id)?);
+        }
+        Ok(self)
+    }
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+    /// Layers the Solana CLI config, then the profile, then env vars and finally the command
+    /// line, each overriding the previous ones.
+    fn layered(
+        global: &GlobalArgs,
+        solana_config: Option<SolanaConfig>,
+        profile: Option<Profile>,
+    ) -> Result<Self> {
+        let mut builder = CliConfigBuilder::new().dry_run(global.dry_run);
+
+        if let Some(config) = solana_config {
+            builder = builder
+                .rpc_url(config.json_rpc_url)
+                .keypair_path(config.keypair_path.into())
+                .commitment(config.commitment);
+        }
+
+        if let Some(profile) = profile {
+            builder = builder.profile(profile)?;
+        }
+
+        builder = builder.env_vars()?;
+
+        if let Some(keypair_path) = global.keypair_path.clone() {
+            builder = builder.keypair_path(keypair_path);
+        }
+
+        if let Some(rpc_url) = global.rpc_url.clone() {
+            builder = builder.rpc_url(rpc_url);
+        }
+
+        if let Some(realm_id) = global.realm {
+            builder = builder.realm_id(realm_id);
+        }
+
+        if let Some(governance_id) = global.governance {
+            builder = builder.governance_id(governance_id);
+        }
+
+        Ok(builder)
+    }
     pub fn build(&self) -> Result<CliConfig> {
         let rpc_url = self
             .json_rpc_url
@@ -78,13 +244,14 @@ impl CliConfigBuilder {
         let recent_blockhash = client.get_latest_blockhash()?;
         let recent_slot = client.get_slot()?;
 
-        let realm_id_var =
-            env::var("REALM_ID").map_err(|_| anyhow!("Missing REALM_ID env var."))?;
-        let governance_id_var =
-            env::var("GOVERNANCE_ID").map_err(|_| anyhow!("Missing GOVERNANCE_ID env var."))?;
-
-        let realm_id = Pubkey::from_str(&realm_id_var)?;
-        let governance_id = Pubkey::from_str(&governance_id_var)?;
+        let realm_id = self.realm_id.ok_or_else(|| {
+            anyhow!("No realm provided, use --realm, REALM_ID or a profile realm")
+        })?;
+        let governance_id = self.governance_id.ok_or_else(|| {
+            anyhow!(
+                "No governance provided, use --governance, GOVERNANCE_ID or a profile governance"
+            )
+        })?;
 
         Ok(CliConfig {
             client,
@@ -93,33 +260,29 @@ impl CliConfigBuilder {
             recent_slot,
             realm_id,
             governance_id,
+            program_id: self.program_id,
+            default_mint_type: self.default_mint_type.clone().unwrap_or(MintType::Council),
+            link_template: self.link_template.clone(),
+            dry_run: self.dry_run,
         })
     }
 }
 
 impl CliConfig {
-    pub fn new(keypair_path: Option<PathBuf>, rpc_url: Option<String>) -> Result<Self> {
-        let mut builder = CliConfigBuilder::new();
-        let solana_config = parse_solana_config();
+    /// Resolves the settings from every source, see `CliConfigBuilder::layered` for the order.
+    pub fn new(global: &GlobalArgs) -> Result<Self> {
+        let builder = CliConfigBuilder::layered(
+            global,
+            parse_solana_config(),
+            load_profile(global.profile.clone())?,
+        )?;
 
-        if let Some(config) = solana_config {
-            builder = builder
-                .rpc_url(config.json_rpc_url)
-                .keypair_path(config.keypair_path.into())
-                .commitment(config.commitment);
-        }
-
-        if let Some(keypair_path) = keypair_path {
-            builder = builder.keypair_path(keypair_path);
-        }
-
-        if let Some(rpc_url) = rpc_url {
-            builder = builder.rpc_url(rpc_url);
-        }
-
-        let config = builder.build()?;
+        builder.build()
+    }
 
-        Ok(config)
+    /// The mint type given on the command line, or else the profile's default.
+    pub fn mint_type(&self, mint_type: Option<MintType>) -> MintType {
+        mint_type.unwrap_or_else(|| self.default_mint_type.clone())
     }
 }
 
@@ -140,3 +303,187 @@ fn parse_solana_config() -> Option<SolanaConfig> {
     }
     None
 }
+
+/// Looks up the named profile, or the default one when no name is given, across the user and
+/// repo-local config files. Fields in the repo-local file override the user's.
+fn load_profile(name: Option<String>) -> Result<Option<Profile>> {
+    let mut configs = vec![];
+
+    if let Some(home_path) = home_dir() {
+        let path = home_path.join(".config").join("themis").join("config.yml");
+        if path.exists() {
+            let config_file = File::open(&path)?;
+            let config: ThemisConfig = serde_yaml::from_reader(config_file)
+                .map_err(|e| anyhow!("Invalid config file {}: {e}", path.display()))?;
+            configs.push(config);
+        }
+    }
+
+    if let Some(path) = find_local_config(&env::current_dir()?) {
+        let config: ThemisConfig = toml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| anyhow!("Invalid config file {}: {e}", path.display()))?;
+        configs.push(config);
+    }
+
+    select_profile(configs, name)
+}
+
+/// Picks the profile out of the config files, later files overriding earlier ones.
+fn select_profile(configs: Vec<ThemisConfig>, name: Option<String>) -> Result<Option<Profile>> {
+    // A profile that was asked for by name must exist, the implicit "default" one may not.
+    let (name, required) =
+        match name.or_else(|| configs.iter().rev().find_map(|c| c.default_profile.clone())) {
+            Some(name) => (name, true),
+            None => ("default".to_string(), false),
+        };
+
+    let profile = configs
+        .into_iter()
+        .filter_map(|mut config| config.profiles.remove(&name))
+        .reduce(Profile::merge);
+
+    if profile.is_none() && required {
+        return Err(anyhow!("Profile {name} not found"));
+    }
+
+    Ok(profile)
+}
+
+fn find_local_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(LOCAL_CONFIG_FILE))
+        .find(|path| path.exists())
+}
+
+fn parse_pubkey(name: &str, value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).map_err(|_| anyhow!("Invalid {name}: {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(default_profile: Option<&str>, profiles: &[(&str, Profile)]) -> ThemisConfig {
+        ThemisConfig {
+            default_profile: default_profile.map(str::to_string),
+            profiles: profiles
+                .iter()
+                .map(|(name, profile)| (name.to_string(), profile.clone()))
+                .collect(),
+        }
+    }
+
+    fn rpc(rpc_url: &str) -> Profile {
+        Profile {
+            rpc_url: Some(rpc_url.to_string()),
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn merge_keeps_fields_the_override_leaves_unset() {
+        let base = Profile {
+            rpc_url: Some("base".to_string()),
+            link_template: Some("link".to_string()),
+            ..Profile::default()
+        };
+        let merged = base.merge(Profile {
+            rpc_url: Some("override".to_string()),
+            mint_type: Some("member".to_string()),
+            ..Profile::default()
+        });
+
+        assert_eq!(merged.rpc_url.as_deref(), Some("override"));
+        assert_eq!(merged.link_template.as_deref(), Some("link"));
+        assert_eq!(merged.mint_type.as_deref(), Some("member"));
+        assert_eq!(merged.realm, None);
+    }
+
+    #[test]
+    fn local_config_overrides_user_config() {
+        let user = config(None, &[("default", rpc("user")), ("other", rpc("other"))]);
+        let local = config(None, &[("default", rpc("local"))]);
+
+        let profile = select_profile(vec![user, local], None).unwrap().unwrap();
+        assert_eq!(profile.rpc_url.as_deref(), Some("local"));
+    }
+
+    #[test]
+    fn selects_the_named_or_default_profile() {
+        let configs = || {
+            vec![
+                config(Some("user"), &[("user", rpc("user")), ("dev", rpc("dev"))]),
+                config(Some("local"), &[("local", rpc("local"))]),
+            ]
+        };
+
+        let named = select_profile(configs(), Some("dev".to_string())).unwrap();
+        assert_eq!(named.unwrap().rpc_url.as_deref(), Some("dev"));
+
+        // The local file's default_profile wins over the user's.
+        let default = select_profile(configs(), None).unwrap();
+        assert_eq!(default.unwrap().rpc_url.as_deref(), Some("local"));
+    }
+
+    #[test]
+    fn only_a_named_profile_is_required() {
+        let configs = || vec![config(None, &[("dev", rpc("dev"))])];
+
+        assert!(select_profile(configs(), Some("prod".to_string())).is_err());
+        assert!(select_profile(configs(), None).unwrap().is_none());
+    }
+
+    #[test]
+    fn later_sources_override_earlier_ones() {
+        let [profile_realm, env_realm, cli_realm] = [(); 3].map(|_| Pubkey::new_unique());
+        let solana_config = || SolanaConfig {
+            json_rpc_url: "solana".to_string(),
+            keypair_path: "solana.json".to_string(),
+            commitment: "finalized".to_string(),
+        };
+        let profile = || Profile {
+            rpc_url: Some("profile".to_string()),
+            realm: Some(profile_realm.to_string()),
+            ..Profile::default()
+        };
+
+        // The only test touching REALM_ID, so it doesn't race with the others.
+        env::remove_var("REALM_ID");
+        let builder =
+            CliConfigBuilder::layered(&GlobalArgs::default(), Some(solana_config()), None).unwrap();
+        assert_eq!(builder.json_rpc_url.as_deref(), Some("solana"));
+        assert_eq!(builder.keypair_path, Some(PathBuf::from("solana.json")));
+
+        let builder = CliConfigBuilder::layered(
+            &GlobalArgs::default(),
+            Some(solana_config()),
+            Some(profile()),
+        )
+        .unwrap();
+        assert_eq!(builder.json_rpc_url.as_deref(), Some("profile"));
+        assert_eq!(builder.keypair_path, Some(PathBuf::from("solana.json")));
+        assert_eq!(builder.realm_id, Some(profile_realm));
+
+        env::set_var("REALM_ID", env_realm.to_string());
+        let builder = CliConfigBuilder::layered(
+            &GlobalArgs::default(),
+            Some(solana_config()),
+            Some(profile()),
+        );
+        let builder_with_cli = CliConfigBuilder::layered(
+            &GlobalArgs {
+                rpc_url: Some("cli".to_string()),
+                realm: Some(cli_realm),
+                ..GlobalArgs::default()
+            },
+            Some(solana_config()),
+            Some(profile()),
+        );
+        env::remove_var("REALM_ID");
+
+        assert_eq!(builder.unwrap().realm_id, Some(env_realm));
+        let builder_with_cli = builder_with_cli.unwrap();
+        assert_eq!(builder_with_cli.realm_id, Some(cli_realm));
+        assert_eq!(builder_with_cli.json_rpc_url.as_deref(), Some("cli"));
+    }
+}
@@ -1,11 +1,18 @@
-use std::env;
+use std::fs::File;
+use std::path::Path;
 use std::str::FromStr;
 
 use anyhow::{anyhow, Result};
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::Deserialize;
 
+use solana_program::loader_upgradeable_instruction::UpgradeableLoaderInstruction;
+use solana_program::program_utils::limited_deserialize;
 use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::SystemInstruction;
 use solana_program::sysvar::{clock::ID as sysvar_clock, rent::ID as rent_sysvar};
+use solana_program::{bpf_loader_upgradeable, system_program};
+use solana_sdk::{bs58, packet::PACKET_DATA_SIZE};
 use spl_governance::instruction::GovernanceInstruction;
 use spl_governance::state::governance::GovernanceConfig;
 use spl_governance::state::proposal_transaction::{AccountMetaData, InstructionData};
@@ -13,18 +20,13 @@ use spl_governance::state::proposal_transaction::{AccountMetaData, InstructionDa
 use crate::{BPF_UPLOADER_ID, GOVERNANCE_PROGRAM_ID};
 
 pub fn create_upgrade_program_instruction(
+    program_id: Pubkey,
+    program_data: Pubkey,
     source_buffer: Pubkey,
     spill_account: Pubkey,
     upgrade_authority: Pubkey,
-) -> Result<InstructionData> {
-    let program_data = Pubkey::from_str(
-        &env::var("PROGRAM_DATA").map_err(|_| anyhow!("Missing PROGRAM_DATA env var."))?,
-    )?;
-    let program_id = Pubkey::from_str(
-        &env::var("PROGRAM_ID").map_err(|_| anyhow!("Missing PROGRAM_ID env var."))?,
-    )?;
-
-    Ok(InstructionData {
+) -> InstructionData {
+    InstructionData {
         program_id: BPF_UPLOADER_ID,
         accounts: vec![
             AccountMetaData {
@@ -64,16 +66,13 @@ pub fn create_upgrade_program_instruction(
             },
         ],
         data: vec![3, 0, 0, 0],
-    })
+    }
 }
 
 pub fn create_set_governance_config_instruction(
+    governance_id: Pubkey,
     config: GovernanceConfig,
 ) -> Result<InstructionData> {
-    let governance_id = Pubkey::from_str(
-        &env::var("GOVERNANCE_ID").map_err(|_| anyhow!("Missing GOVERNANCE_ID env var."))?,
-    )?;
-
     let instruction = GovernanceInstruction::SetGovernanceConfig { config };
 
     Ok(InstructionData {
@@ -86,3 +85,396 @@ pub fn create_set_governance_config_instruction(
         data: instruction.try_to_vec()?,
     })
 }
+
+/// A proposal option read from an instructions file, with its transactions in order.
+#[derive(Debug)]
+pub struct OptionSpec {
+    pub label: Option<String>,
+    pub transactions: Vec<TransactionSpec>,
+}
+
+/// Instructions executed together in a single proposal transaction.
+#[derive(Debug)]
+pub struct TransactionSpec {
+    pub hold_up_time: Option<u32>,
+    pub instructions: Vec<InstructionData>,
+}
+
+/// The file either lists `instructions` for a single transaction, `transactions` for a single
+/// option, or `options` each with their own transactions.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InstructionsFile {
+    #[serde(default)]
+    instructions: Vec<InstructionEntry>,
+    #[serde(default)]
+    transactions: Vec<TransactionEntry>,
+    #[serde(default)]
+    options: Vec<OptionEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OptionEntry {
+    label: Option<String>,
+    transactions: Vec<TransactionEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransactionEntry {
+    hold_up_time: Option<u32>,
+    instructions: Vec<InstructionEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InstructionEntry {
+    program_id: String,
+    #[serde(default)]
+    accounts: Vec<AccountEntry>,
+    #[serde(default)]
+    data: String,
+    #[serde(default)]
+    encoding: DataEncoding,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AccountEntry {
+    pubkey: String,
+    #[serde(default)]
+    is_signer: bool,
+    #[serde(default)]
+    is_writable: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DataEncoding {
+    #[default]
+    Base58,
+    Base64,
+}
+
+/// Reads a JSON or YAML file describing the instructions to insert into a proposal.
+pub fn read_instructions_file(path: &Path) -> Result<Vec<OptionSpec>> {
+    let file = File::open(path)
+        .map_err(|e| anyhow!("Unable to open instructions file {}: {e}", path.display()))?;
+
+    // YAML is a superset of JSON so the same parser handles both formats.
+    let instructions_file: InstructionsFile = serde_yaml::from_reader(file)
+        .map_err(|e| anyhow!("Unable to parse instructions file {}: {e}", path.display()))?;
+
+    let options = match (
+        instructions_file.instructions.is_empty(),
+        instructions_file.transactions.is_empty(),
+        instructions_file.options.is_empty(),
+    ) {
+        (false, true, true) => vec![OptionEntry {
+            label: None,
+            transactions: vec![TransactionEntry {
+                hold_up_time: None,
+                instructions: instructions_file.instructions,
+            }],
+        }],
+        (true, false, true) => vec![OptionEntry {
+            label: None,
+            transactions: instructions_file.transactions,
+        }],
+        (true, true, false) => instructions_file.options,
+        _ => {
+            return Err(anyhow!(
+                "{} must contain exactly one of instructions, transactions or options",
+                path.display()
+            ))
+        }
+    };
+
+    options
+        .into_iter()
+        .map(|option| {
+            let transactions = option
+                .transactions
+                .into_iter()
+                .map(|transaction| {
+                    if transaction.instructions.is_empty() {
+                        return Err(anyhow!("Empty transaction found in {}", path.display()));
+                    }
+
+                    Ok(TransactionSpec {
+                        hold_up_time: transaction.hold_up_time,
+                        instructions: transaction
+                            .instructions
+                            .into_iter()
+                            .map(parse_instruction_entry)
+                            .collect::<Result<_>>()?,
+                    })
+                })
+                .collect::<Result<_>>()?;
+
+            Ok(OptionSpec {
+                label: option.label,
+                transactions,
+            })
+        })
+        .collect()
+}
+
+fn parse_instruction_entry(entry: InstructionEntry) -> Result<InstructionData> {
+    let accounts = entry
+        .accounts
+        .into_iter()
+        .map(|account| {
+            Ok(AccountMetaData {
+                pubkey: Pubkey::from_str(&account.pubkey)?,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let data = match entry.encoding {
+        DataEncoding::Base58 => bs58::decode(&entry.data).into_vec()?,
+        DataEncoding::Base64 => base64::decode(&entry.data)?,
+    };
+
+    Ok(InstructionData {
+        program_id: Pubkey::from_str(&entry.program_id)?,
+        accounts,
+        data,
+    })
+}
+
+/// A program to upgrade, read from an upgrade manifest.
+#[derive(Debug)]
+pub struct UpgradeSpec {
+    pub program_id: Pubkey,
+    pub buffer: Pubkey,
+    pub spill_account: Option<Pubkey>,
+    pub expected_hash: Option<String>,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UpgradeManifest {
+    programs: Vec<UpgradeEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UpgradeEntry {
+    program_id: String,
+    buffer: String,
+    spill_account: Option<String>,
+    expected_hash: Option<String>,
+    label: Option<String>,
+}
+
+/// Reads a JSON or YAML manifest listing the programs to upgrade together.
+pub fn read_upgrade_manifest(path: &Path) -> Result<Vec<UpgradeSpec>> {
+    let file =
+        File::open(path).map_err(|e| anyhow!("Unable to open manifest {}: {e}", path.display()))?;
+
+    let manifest: UpgradeManifest = serde_yaml::from_reader(file)
+        .map_err(|e| anyhow!("Unable to parse manifest {}: {e}", path.display()))?;
+
+    if manifest.programs.is_empty() {
+        return Err(anyhow!("No programs found in {}", path.display()));
+    }
+
+    manifest
+        .programs
+        .into_iter()
+        .map(|entry| {
+            Ok(UpgradeSpec {
+                program_id: Pubkey::from_str(&entry.program_id)?,
+                buffer: Pubkey::from_str(&entry.buffer)?,
+                spill_account: entry
+                    .spill_account
+                    .map(|spill_account| Pubkey::from_str(&spill_account))
+                    .transpose()?,
+                expected_hash: entry.expected_hash,
+                label: entry.label,
+            })
+        })
+        .collect()
+}
+
+/// Formats an instruction for review, decoding the data of the programs Themis works with.
+/// Transactions inserted into a proposal are decoded along with their instructions.
+pub fn format_instruction(instruction: &InstructionData, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    let mut lines = vec![];
+
+    match program_name(&instruction.program_id) {
+        Some(name) => lines.push(format!("{pad}Program: {} ({name})", instruction.program_id)),
+        None => lines.push(format!("{pad}Program: {}", instruction.program_id)),
+    }
+
+    lines.push(format!("{pad}Accounts:"));
+    for account in &instruction.accounts {
+        let mut flags = vec![];
+        if account.is_signer {
+            flags.push("signer");
+        }
+        if account.is_writable {
+            flags.push("writable");
+        }
+        lines.push(format!("{pad}  {} [{}]", account.pubkey, flags.join(", ")));
+    }
+
+    let program_id = instruction.program_id;
+    let data = instruction.data.as_slice();
+
+    if program_id == GOVERNANCE_PROGRAM_ID {
+        match GovernanceInstruction::try_from_slice(data) {
+            Ok(GovernanceInstruction::InsertTransaction {
+                option_index,
+                index,
+                hold_up_time,
+                instructions,
+            }) => {
+                lines.push(format!(
+                    "{pad}Instruction: InsertTransaction {{ option_index: {option_index}, index: {index}, hold_up_time: {hold_up_time} }}"
+                ));
+                for (i, instruction) in instructions.iter().enumerate() {
+                    lines.push(format!("{pad}  Instruction #{i}:"));
+                    lines.push(format_instruction(instruction, indent + 4));
+                }
+            }
+            Ok(GovernanceInstruction::SetGovernanceConfig { config }) => {
+                lines.push(format!("{pad}Instruction: SetGovernanceConfig"));
+                lines.push(indent_lines(&format!("{:#?}", config), indent + 2));
+            }
+            Ok(decoded) => lines.push(format!("{pad}Instruction: {:?}", decoded)),
+            Err(_) => lines.push(format!("{pad}Data: {}", bs58::encode(data).into_string())),
+        }
+    } else if program_id == bpf_loader_upgradeable::id() {
+        match limited_deserialize::<UpgradeableLoaderInstruction>(data, PACKET_DATA_SIZE as u64) {
+            // The program bytes aren't useful to a reviewer.
+            Ok(UpgradeableLoaderInstruction::Write { offset, bytes }) => lines.push(format!(
+                "{pad}Instruction: Write {{ offset: {offset}, len: {} }}",
+                bytes.len()
+            )),
+            Ok(decoded) => lines.push(format!("{pad}Instruction: {:?}", decoded)),
+            Err(_) => lines.push(format!("{pad}Data: {}", bs58::encode(data).into_string())),
+        }
+    } else if program_id == system_program::id() {
+        match limited_deserialize::<SystemInstruction>(data, PACKET_DATA_SIZE as u64) {
+            Ok(decoded) => lines.push(format!("{pad}Instruction: {:?}", decoded)),
+            Err(_) => lines.push(format!("{pad}Data: {}", bs58::encode(data).into_string())),
+        }
+    } else {
+        lines.push(format!("{pad}Data: {}", bs58::encode(data).into_string()));
+    }
+
+    lines.join("\n")
+}
+
+fn program_name(program_id: &Pubkey) -> Option<&'static str> {
+    if *program_id == GOVERNANCE_PROGRAM_ID {
+        Some("Governance")
+    } else if *program_id == bpf_loader_upgradeable::id() {
+        Some("BPF Upgradeable Loader")
+    } else if *program_id == system_program::id() {
+        Some("System Program")
+    } else {
+        None
+    }
+}
+
+fn indent_lines(text: &str, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    text.lines()
+        .map(|line| format!("{pad}{line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use super::*;
+
+    const INSTRUCTION: &str = "{ program_id: 11111111111111111111111111111111, data: '' }";
+
+    fn read(name: &str, contents: &str) -> Result<Vec<OptionSpec>> {
+        let path: PathBuf =
+            env::temp_dir().join(format!("themis-{}-{name}.yml", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let options = read_instructions_file(&path);
+        fs::remove_file(&path).unwrap();
+        options
+    }
+
+    #[test]
+    fn reads_instructions_as_a_single_transaction() {
+        let options = read(
+            "instructions",
+            &format!("instructions: [{INSTRUCTION}, {INSTRUCTION}]"),
+        )
+        .unwrap();
+
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].label, None);
+        assert_eq!(options[0].transactions.len(), 1);
+        assert_eq!(options[0].transactions[0].instructions.len(), 2);
+    }
+
+    #[test]
+    fn reads_transactions_as_a_single_option() {
+        let options = read(
+            "transactions",
+            &format!(
+                "transactions:\n  - instructions: [{INSTRUCTION}]\n  \
+                 - hold_up_time: 60\n    instructions: [{INSTRUCTION}]"
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].transactions.len(), 2);
+        assert_eq!(options[0].transactions[1].hold_up_time, Some(60));
+    }
+
+    #[test]
+    fn reads_options() {
+        let options = read(
+            "options",
+            &format!(
+                "options:\n  - label: A\n    transactions: [{{ instructions: [{INSTRUCTION}] }}]\n  \
+                 - label: B\n    transactions: [{{ instructions: [{INSTRUCTION}] }}]"
+            ),
+        )
+        .unwrap();
+
+        let labels: Vec<_> = options.iter().map(|o| o.label.as_deref()).collect();
+        assert_eq!(labels, [Some("A"), Some("B")]);
+    }
+
+    #[test]
+    fn rejects_none_or_several_of_instructions_transactions_and_options() {
+        let contents = [
+            "{}".to_string(),
+            format!("instructions: [{INSTRUCTION}]\ntransactions: [{{ instructions: [{INSTRUCTION}] }}]"),
+            format!(
+                "instructions: [{INSTRUCTION}]\noptions: [{{ transactions: [{{ instructions: [{INSTRUCTION}] }}] }}]"
+            ),
+            format!(
+                "transactions: [{{ instructions: [{INSTRUCTION}] }}]\noptions: [{{ transactions: [{{ instructions: [{INSTRUCTION}] }}] }}]"
+            ),
+        ];
+
+        for (i, contents) in contents.iter().enumerate() {
+            let err = read(&format!("invalid-{i}"), contents).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains("exactly one of instructions, transactions or options"),
+                "{contents}: {err}"
+            );
+        }
+    }
+}
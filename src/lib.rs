@@ -1,11 +1,16 @@
+use anyhow::{anyhow, Result};
 use solana_program::pubkey;
 use solana_sdk::pubkey::Pubkey;
-use spl_governance::state::vote_record::{Vote as SplVote, VoteChoice};
+use spl_governance::state::{
+    proposal::VoteType,
+    vote_record::{Vote as SplVote, VoteChoice},
+};
 use std::{fmt, str::FromStr};
 
 pub mod args;
 pub mod config;
 pub mod instruction;
+pub mod output;
 pub mod processor;
 
 pub enum Cluster {
@@ -40,7 +45,7 @@ impl fmt::Display for Vote {
 impl FromStr for Vote {
     type Err = anyhow::Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "yes" | "yay" | "yeah" | "true" | "yea" => Ok(Vote::Yes),
             "no" | "nay" | "nah" => Ok(Vote::No),
@@ -49,17 +54,145 @@ impl FromStr for Vote {
     }
 }
 
-impl From<Vote> for SplVote {
-    fn from(vote: Vote) -> Self {
-        match vote {
-            Vote::Yes => SplVote::Approve(vec![VoteChoice {
-                rank: 0,
-                weight_percentage: 100,
-            }]),
-            Vote::No => SplVote::Deny,
+impl Vote {
+    /// Approving a proposal needs a choice for every option, so approve the requested options,
+    /// or the only option when there is just one. A single choice proposal takes one at most.
+    pub fn into_spl_vote(
+        self,
+        vote_type: &VoteType,
+        options_count: usize,
+        approved_options: &[u8],
+    ) -> Result<SplVote> {
+        match self {
+            Vote::Yes => {
+                let mut approved_options = match (approved_options.is_empty(), options_count) {
+                    (true, 1) => vec![0],
+                    (true, _) => {
+                        return Err(anyhow!(
+                            "Proposal has {options_count} options, specify which to approve"
+                        ))
+                    }
+                    (false, _) => approved_options.to_vec(),
+                };
+                approved_options.sort_unstable();
+                approved_options.dedup();
+
+                if *vote_type == VoteType::SingleChoice && approved_options.len() > 1 {
+                    return Err(anyhow!(
+                        "Proposal is single choice, approve only one of its options"
+                    ));
+                }
+
+                if let Some(option) = approved_options
+                    .iter()
+                    .find(|option| **option as usize >= options_count)
+                {
+                    return Err(anyhow!(
+                        "Option {option} does not exist, the proposal has {options_count} options"
+                    ));
+                }
+
+                Ok(SplVote::Approve(
+                    (0..options_count)
+                        .map(|i| VoteChoice {
+                            rank: 0,
+                            weight_percentage: if approved_options.contains(&(i as u8)) {
+                                100
+                            } else {
+                                0
+                            },
+                        })
+                        .collect(),
+                ))
+            }
+            Vote::No => Ok(SplVote::Deny),
         }
     }
 }
 
 pub const GOVERNANCE_PROGRAM_ID: Pubkey = pubkey!("mrgTA4fqsDqtvizQBoTMGXosiwruTmu2yXZxmPNLKiJ");
 pub const BPF_UPLOADER_ID: Pubkey = pubkey!("BPFLoaderUpgradeab1e11111111111111111111111");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn multi_choice(options: u8) -> VoteType {
+        VoteType::MultiChoice {
+            max_voter_options: options,
+            max_winning_options: options,
+        }
+    }
+
+    fn weights(vote: SplVote) -> Vec<u8> {
+        match vote {
+            SplVote::Approve(choices) => choices
+                .iter()
+                .map(|choice| choice.weight_percentage)
+                .collect(),
+            _ => panic!("expected an approve vote"),
+        }
+    }
+
+    #[test]
+    fn yes_approves_the_only_option_by_default() {
+        assert_eq!(
+            weights(
+                Vote::Yes
+                    .into_spl_vote(&VoteType::SingleChoice, 1, &[])
+                    .unwrap()
+            ),
+            [100]
+        );
+    }
+
+    #[test]
+    fn yes_approves_the_requested_options() {
+        assert_eq!(
+            weights(
+                Vote::Yes
+                    .into_spl_vote(&multi_choice(3), 3, &[0, 2])
+                    .unwrap()
+            ),
+            [100, 0, 100]
+        );
+    }
+
+    #[test]
+    fn yes_approves_one_option_of_a_single_choice_proposal() {
+        assert_eq!(
+            weights(
+                Vote::Yes
+                    .into_spl_vote(&VoteType::SingleChoice, 3, &[1, 1])
+                    .unwrap()
+            ),
+            [0, 100, 0]
+        );
+
+        let err = Vote::Yes
+            .into_spl_vote(&VoteType::SingleChoice, 3, &[0, 2])
+            .unwrap_err();
+        assert!(err.to_string().contains("single choice"));
+    }
+
+    #[test]
+    fn yes_needs_options_when_there_are_several() {
+        assert!(Vote::Yes.into_spl_vote(&multi_choice(2), 2, &[]).is_err());
+    }
+
+    #[test]
+    fn yes_rejects_options_out_of_range() {
+        let err = Vote::Yes
+            .into_spl_vote(&multi_choice(2), 2, &[2])
+            .unwrap_err();
+        assert!(err.to_string().contains("Option 2 does not exist"));
+    }
+
+    #[test]
+    fn no_denies_regardless_of_options() {
+        assert!(matches!(
+            Vote::No.into_spl_vote(&multi_choice(3), 3, &[1]).unwrap(),
+            SplVote::Deny
+        ));
+    }
+}
@@ -2,14 +2,18 @@ use anyhow::Result;
 use clap::Parser;
 
 use log::debug;
-use spl_governance::state::proposal::VoteType;
 use themis::{
-    args::{self, Commands},
+    args::{self, BufferCommands, Commands, ProgramCommands, ProposalCommands},
+    output::print_output,
     processor::{
-        cancel, close_buffers, deposit, execute, get_buffers, get_gov_config, update_config,
-        upgrade_program, vote, withdraw, CancelArgs, CloseBuffersArgs, DepositArgs, ExecuteArgs,
-        GetBuffersArgs, GetGovConfigArgs, UpdateConfigArgs, UpgradeProgramArgs, VoteArgs,
-        WithdrawArgs,
+        buffer_hash, buffer_report, cancel, close_buffers, close_program, deposit, execute,
+        extend_program, get_gov_config, list_programs, list_proposals, program_hash, propose,
+        set_authority, show_proposal, transfer_authority, update_config, upgrade_program, vote,
+        withdraw, write_buffer, BufferHashArgs, BufferReportArgs, CancelArgs, CloseBuffersArgs,
+        CloseProgramArgs, DepositArgs, ExecuteArgs, ExtendProgramArgs, GetGovConfigArgs,
+        ListProgramsArgs, ListProposalsArgs, ProgramHashArgs, ProposeArgs, SetAuthorityArgs,
+        ShowProposalArgs, TransferAuthorityArgs, UpdateConfigArgs, UpgradeProgramArgs, VoteArgs,
+        WithdrawArgs, WriteBufferArgs,
     },
 };
 
@@ -22,74 +26,262 @@ fn main() -> Result<()> {
 
     let args = args::Args::parse();
 
-    let keypair_path = args.keypair_path.clone();
-    let rpc_url = args.rpc_url.clone();
+    let global = args.global;
+    let output = args.output;
 
     match args.command {
         Commands::UpgradeProgram {
+            program_id,
             source_buffer,
+            manifest,
+            option_per_program,
             spill_account,
             name,
             description,
             mint_type,
             options,
-        } => upgrade_program(UpgradeProgramArgs {
-            keypair_path,
-            rpc_url,
-            source_buffer,
-            spill_account,
+            choice_type,
+            resume,
+            no_extend,
+            expected_hash,
+            auto_description,
+            git_commit,
+            link_template,
+        } => print_output(
+            &upgrade_program(UpgradeProgramArgs {
+                global,
+                program_id,
+                source_buffer,
+                manifest,
+                option_per_program,
+                spill_account,
+                name,
+                description,
+                auto_description,
+                mint_type,
+                options,
+                choice_type,
+                resume,
+                no_extend,
+                expected_hash,
+                git_commit,
+                link_template,
+            })?,
+            output,
+        ),
+        Commands::ExtendProgram {
+            program_id,
+            bytes,
             name,
             description,
             mint_type,
-            vote_type: VoteType::SingleChoice,
-            options,
-        }),
-        Commands::Vote {
+        } => print_output(
+            &extend_program(ExtendProgramArgs {
+                global,
+                program_id,
+                bytes,
+                name,
+                description,
+                mint_type,
+            })?,
+            output,
+        ),
+        Commands::Propose {
+            instructions_file,
             proposal_id,
-            vote_choice,
+            name,
+            description,
             mint_type,
-            latest,
-        } => vote(VoteArgs {
-            keypair_path,
-            rpc_url,
+            options,
+            choice_type,
+            draft,
+            resume,
+        } => print_output(
+            &propose(ProposeArgs {
+                global,
+                instructions_file,
+                proposal_id,
+                name,
+                description,
+                mint_type,
+                choice_type,
+                options,
+                draft,
+                resume,
+            })?,
+            output,
+        ),
+        Commands::Vote {
             proposal_id,
             vote_choice,
+            approve_options,
             mint_type,
             latest,
-        }),
+        } => print_output(
+            &vote(VoteArgs {
+                global,
+                proposal_id,
+                vote_choice,
+                approve_options,
+                mint_type,
+                latest,
+            })?,
+            output,
+        ),
+        Commands::Proposal { command } => match command {
+            ProposalCommands::Show {
+                proposal_id,
+                latest,
+                mint_type,
+            } => print_output(
+                &show_proposal(ShowProposalArgs {
+                    global,
+                    proposal_id,
+                    latest,
+                    mint_type,
+                })?,
+                output,
+            ),
+            ProposalCommands::List { state, mint_type } => print_output(
+                &list_proposals(ListProposalsArgs {
+                    global,
+                    states: state,
+                    mint_type,
+                })?,
+                output,
+            ),
+        },
+        Commands::Buffer { command } => match command {
+            BufferCommands::Write {
+                program_path,
+                resume,
+            } => print_output(
+                &write_buffer(WriteBufferArgs {
+                    global,
+                    program_path,
+                    resume,
+                })?,
+                output,
+            ),
+            BufferCommands::Hash { buffer } => {
+                print_output(&buffer_hash(BufferHashArgs { global, buffer })?, output)
+            }
+        },
+        Commands::Program { command } => match command {
+            ProgramCommands::List => {
+                print_output(&list_programs(ListProgramsArgs { global })?, output)
+            }
+            ProgramCommands::TransferAuthority {
+                program_id,
+                create_governance,
+                mint_type,
+            } => print_output(
+                &transfer_authority(TransferAuthorityArgs {
+                    global,
+                    program_id,
+                    create_governance,
+                    mint_type,
+                })?,
+                output,
+            ),
+            ProgramCommands::SetAuthority {
+                new_authority,
+                program_id,
+                name,
+                description,
+                mint_type,
+                yes,
+            } => print_output(
+                &set_authority(SetAuthorityArgs {
+                    global,
+                    program_id,
+                    new_authority: Some(new_authority),
+                    name,
+                    description,
+                    mint_type,
+                    yes,
+                })?,
+                output,
+            ),
+            ProgramCommands::MakeImmutable {
+                program_id,
+                name,
+                description,
+                mint_type,
+                yes,
+            } => print_output(
+                &set_authority(SetAuthorityArgs {
+                    global,
+                    program_id,
+                    new_authority: None,
+                    name,
+                    description,
+                    mint_type,
+                    yes,
+                })?,
+                output,
+            ),
+            ProgramCommands::Close {
+                program_id,
+                recipient,
+                name,
+                description,
+                mint_type,
+                yes,
+            } => print_output(
+                &close_program(CloseProgramArgs {
+                    global,
+                    program_id,
+                    recipient,
+                    name,
+                    description,
+                    mint_type,
+                    yes,
+                })?,
+                output,
+            ),
+            ProgramCommands::Hash { program_id } => print_output(
+                &program_hash(ProgramHashArgs { global, program_id })?,
+                output,
+            ),
+        },
         Commands::Execute {
             proposal_id,
             mint_type,
             latest,
-        } => execute(ExecuteArgs {
-            keypair_path,
-            rpc_url,
-            proposal_id,
-            mint_type,
-            latest,
-        }),
+        } => print_output(
+            &execute(ExecuteArgs {
+                global,
+                proposal_id,
+                mint_type,
+                latest,
+            })?,
+            output,
+        ),
         Commands::Cancel {
             proposal_id,
             mint_type,
             latest,
-        } => cancel(CancelArgs {
-            keypair_path,
-            rpc_url,
-            proposal_id,
-            mint_type,
-            latest,
-        }),
-        Commands::Deposit { amount, mint_type } => deposit(DepositArgs {
-            keypair_path,
-            rpc_url,
-            amount,
-            mint_type,
-        }),
-        Commands::Withdraw { mint_type } => withdraw(WithdrawArgs {
-            keypair_path,
-            rpc_url,
-            mint_type,
-        }),
+        } => print_output(
+            &cancel(CancelArgs {
+                global,
+                proposal_id,
+                mint_type,
+                latest,
+            })?,
+            output,
+        ),
+        Commands::Deposit { amount, mint_type } => print_output(
+            &deposit(DepositArgs {
+                global,
+                amount,
+                mint_type,
+            })?,
+            output,
+        ),
+        Commands::Withdraw { mint_type } => {
+            print_output(&withdraw(WithdrawArgs { global, mint_type })?, output)
+        }
         Commands::UpdateConfig {
             mint_type,
             vote_threshold_percentage,
@@ -98,51 +290,63 @@ fn main() -> Result<()> {
             max_voting_time,
             proposal_cool_off_time,
             min_comunity_weight_to_create_proposal,
-        } => update_config(UpdateConfigArgs {
-            keypair_path,
-            rpc_url,
-            mint_type,
-            vote_threshold_percentage,
-            min_council_weight_to_create_proposal,
-            min_transaction_hold_up_time,
-            max_voting_time,
-            proposal_cool_off_time,
-            min_comunity_weight_to_create_proposal,
-        }),
-        Commands::GetGovConfig {} => get_gov_config(GetGovConfigArgs {
-            keypair_path,
-            rpc_url,
-        }),
-        Commands::GetBuffers { authority } => {
-            let buffers = get_buffers(GetBuffersArgs {
-                keypair_path,
-                rpc_url,
-                authority,
-            })?;
-
-            println!("Buffers: {:#?}", buffers);
-
-            Ok(())
+        } => print_output(
+            &update_config(UpdateConfigArgs {
+                global,
+                mint_type,
+                vote_threshold_percentage,
+                min_council_weight_to_create_proposal,
+                min_transaction_hold_up_time,
+                max_voting_time,
+                proposal_cool_off_time,
+                min_comunity_weight_to_create_proposal,
+            })?,
+            output,
+        ),
+        Commands::GetGovConfig => {
+            print_output(&get_gov_config(GetGovConfigArgs { global })?, output)
         }
-        Commands::CloseBuffers {
+        Commands::GetBuffers {
             authority,
-            recipient,
-            spill_account,
-            name,
-            description,
-            mint_type,
-            options,
-        } => close_buffers(CloseBuffersArgs {
-            keypair_path,
-            rpc_url,
+            min_age,
+            min_size,
+            sort,
+        } => print_output(
+            &buffer_report(BufferReportArgs {
+                global,
+                authority,
+                min_age,
+                min_size,
+                sort,
+            })?,
+            output,
+        ),
+        Commands::CloseBuffers {
             authority,
             recipient,
             spill_account,
             name,
             description,
             mint_type,
-            vote_type: VoteType::SingleChoice,
             options,
-        }),
+            choice_type,
+            resume,
+            include_own,
+        } => print_output(
+            &close_buffers(CloseBuffersArgs {
+                global,
+                authority,
+                recipient,
+                spill_account,
+                name,
+                description,
+                mint_type,
+                options,
+                choice_type,
+                resume,
+                include_own,
+            })?,
+            output,
+        ),
     }
 }
@@ -0,0 +1,104 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use solana_sdk::signature::Signature;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            _ => Err(anyhow!("Invalid output format")),
+        }
+    }
+}
+
+/// Prints a command result as human readable text or serialized for scripts.
+pub fn print_output<T>(output: &T, format: OutputFormat) -> Result<()>
+where
+    T: Serialize + fmt::Display,
+{
+    match format {
+        OutputFormat::Text => println!("{output}"),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(output)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(output)?),
+    }
+
+    Ok(())
+}
+
+/// Serializes values such as pubkeys and signatures as their base58 strings rather than the
+/// byte arrays their own serde implementations produce.
+pub mod display_fromstr {
+    use std::{fmt::Display, str::FromStr};
+
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Serializes the signature of a transaction that may not have been sent, as in dry run mode,
+/// as its base58 string or null.
+pub mod option_signature {
+    use serde::Serializer;
+    use solana_sdk::signature::Signature;
+
+    pub fn serialize<S>(signature: &Option<Signature>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match signature {
+            Some(signature) => serializer.collect_str(signature),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Shows the signature of a transaction, or that it wasn't sent in dry run mode.
+pub fn format_signature(signature: &Option<Signature>) -> String {
+    match signature {
+        Some(signature) => signature.to_string(),
+        None => "not sent (dry run)".to_string(),
+    }
+}
+
+/// The result of a command that sends a single transaction.
+#[derive(Debug, Serialize)]
+pub struct SignatureOutput {
+    #[serde(with = "option_signature")]
+    pub signature: Option<Signature>,
+}
+
+impl fmt::Display for SignatureOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_signature(&self.signature))
+    }
+}
@@ -1,41 +1,22 @@
 use super::*;
 
 pub struct CancelArgs {
-    pub keypair_path: Option<PathBuf>,
-    pub rpc_url: Option<String>,
+    pub global: GlobalArgs,
     pub proposal_id: Option<Pubkey>,
     pub latest: bool,
-    pub mint_type: MintType,
+    pub mint_type: Option<MintType>,
 }
 
-pub fn cancel(args: CancelArgs) -> Result<()> {
-    let config = config::CliConfig::new(args.keypair_path, args.rpc_url)?;
+pub fn cancel(args: CancelArgs) -> Result<SignatureOutput> {
+    let config = config::CliConfig::new(&args.global)?;
 
     let realm: RealmV2 = get_governance_state(&config.client, &config.realm_id)?;
 
-    let governing_token_mint = match args.mint_type {
-        MintType::Member => realm.community_mint,
-        MintType::Council => realm
-            .config
-            .council_mint
-            .ok_or_else(|| anyhow!("Council mint not found"))?,
-    };
+    let mint_type = config.mint_type(args.mint_type);
 
-    let proposal_id = if args.latest {
-        let governance: GovernanceV2 = get_governance_state(&config.client, &config.governance_id)?;
-        let proposal_index = governance.proposals_count - 1;
+    let governing_token_mint = mint_type.governing_token_mint(&realm)?;
 
-        get_proposal_address(
-            &GOVERNANCE_PROGRAM_ID,
-            &config.governance_id,
-            &governing_token_mint,
-            &proposal_index.to_le_bytes(),
-        )
-    } else if let Some(proposal_id) = args.proposal_id {
-        proposal_id
-    } else {
-        return Err(anyhow!("Either --latest or --proposal-id must be provided"));
-    };
+    let proposal_id = resolve_proposal_id(&config, &mint_type, args.proposal_id, args.latest)?;
 
     let proposal_owner_record = get_token_owner_record_address(
         &GOVERNANCE_PROGRAM_ID,
@@ -60,9 +41,7 @@ pub fn cancel(args: CancelArgs) -> Result<()> {
         config.client.get_latest_blockhash()?,
     );
 
-    config
-        .client
-        .send_and_confirm_transaction_with_spinner(&tx)?;
+    let signature = send_transaction(&config, &tx)?;
 
-    Ok(())
+    Ok(SignatureOutput { signature })
 }
@@ -1,164 +1,283 @@
-use solana_program::instruction::Instruction;
-use spl_governance::state::proposal_transaction::{AccountMetaData, InstructionData};
+use std::collections::HashMap;
+
+use solana_program::{
+    loader_upgradeable_instruction::UpgradeableLoaderInstruction,
+    program_utils::limited_deserialize,
+};
+use solana_sdk::native_token::lamports_to_sol;
 
 use super::*;
 
-#[derive(Debug, Clone)]
 pub struct CloseBuffersArgs {
-    pub keypair_path: Option<PathBuf>,
-    pub rpc_url: Option<String>,
+    pub global: GlobalArgs,
     pub authority: Pubkey,
     pub recipient: Pubkey,
     pub spill_account: Option<Pubkey>,
-    pub name: String,
-    pub description: String,
-    pub mint_type: MintType,
-    pub vote_type: VoteType,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub mint_type: Option<MintType>,
+    pub choice_type: Option<ChoiceType>,
+    pub resume: Option<Pubkey>,
     pub options: Vec<String>,
+    pub include_own: bool,
+}
+
+/// A buffer left open because a pending proposal upgrades a program from it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedBuffer {
+    #[serde(with = "display_fromstr")]
+    pub buffer: Pubkey,
+    #[serde(with = "display_fromstr")]
+    pub proposal: Pubkey,
+    pub proposal_state: String,
 }
 
-const BATCH_SIZE: usize = 10;
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuffersClosed {
+    /// Nothing was sent, the buffers are only those that would be closed.
+    pub dry_run: bool,
+    /// Buffers closed directly because the local keypair is their authority.
+    #[serde(serialize_with = "serialize_pubkeys")]
+    pub closed: Vec<Pubkey>,
+    pub reclaimed_lamports: u64,
+    #[serde(serialize_with = "serialize_pubkeys")]
+    pub proposed: Vec<Pubkey>,
+    /// Lamports returned once the proposal closing the remaining buffers is executed.
+    pub proposed_lamports: u64,
+    pub proposal: Option<ProposalCreated>,
+    pub skipped: Vec<SkippedBuffer>,
+}
 
-pub fn close_buffers(args: CloseBuffersArgs) -> Result<()> {
-    let config = config::CliConfig::new(args.keypair_path.clone(), args.rpc_url.clone())?;
+impl fmt::Display for BuffersClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for skipped in &self.skipped {
+            writeln!(
+                f,
+                "Skipped buffer {}: used by {} proposal {}",
+                skipped.buffer, skipped.proposal_state, skipped.proposal
+            )?;
+        }
+        let (closed, proposed) = if self.dry_run {
+            ("Dry run, would close", "Dry run, would propose closing")
+        } else {
+            ("Closed", "Proposed closing")
+        };
+        write!(
+            f,
+            "{closed} {} buffer(s), reclaiming {} SOL",
+            self.closed.len(),
+            lamports_to_sol(self.reclaimed_lamports)
+        )?;
+        if let Some(proposal) = &self.proposal {
+            write!(
+                f,
+                "\n{proposed} {} buffer(s), reclaiming {} SOL once executed\n{proposal}",
+                self.proposed.len(),
+                lamports_to_sol(self.proposed_lamports)
+            )?;
+        }
+        Ok(())
+    }
+}
 
-    let buffers = get_buffers(args.clone().into())?;
+pub fn close_buffers(args: CloseBuffersArgs) -> Result<BuffersClosed> {
+    let config = config::CliConfig::new(&args.global)?;
 
-    let realm = get_realm_data(&config.client, &config.realm_id)?;
-    let governance = get_governance_data(&config.client, &config.governance_id)?;
+    let signer = config.keypair.pubkey();
 
-    let governing_token_mint = match args.mint_type {
-        MintType::Member => realm.community_mint,
-        MintType::Council => realm
-            .config
-            .council_mint
-            .ok_or_else(|| anyhow!("Council mint not found"))?,
+    // Buffers the local keypair controls don't need a vote to be closed.
+    let (own_buffers, governance_buffers) = if args.authority == signer {
+        (get_buffers(&config.client, &signer)?, vec![])
+    } else if args.include_own {
+        (
+            get_buffers(&config.client, &signer)?,
+            get_buffers(&config.client, &args.authority)?,
+        )
+    } else {
+        (vec![], get_buffers(&config.client, &args.authority)?)
     };
 
-    let proposal_index: u32 = governance.proposals_count;
-
-    let proposal_owner_record = get_token_owner_record_address(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.realm_id,
-        &governing_token_mint,
-        &config.keypair.pubkey(),
-    );
+    // A buffer is consumed by the upgrade that uses it, so closing one a pending proposal
+    // points at would make that proposal fail on execution. The loader only upgrades from a
+    // buffer whose authority is the upgrade authority, the governance, so the local keypair's
+    // buffers can't be in use.
+    let protected = get_protected_buffers(&config, &governance_buffers)?;
+    let mut skipped = vec![];
+    let governance_buffers: Vec<UpgradeableBuffer> = governance_buffers
+        .into_iter()
+        .filter(|buffer| match protected.get(&buffer.address) {
+            Some((proposal, state)) => {
+                skipped.push(SkippedBuffer {
+                    buffer: buffer.address,
+                    proposal: *proposal,
+                    proposal_state: format!("{state:?}"),
+                });
+                false
+            }
+            None => true,
+        })
+        .collect();
 
-    let mut instructions = vec![];
+    // Checked before anything is sent, so a missing flag doesn't leave the work half done.
+    let proposal_text = if governance_buffers.is_empty() {
+        None
+    } else {
+        match (args.name, args.description) {
+            (Some(name), Some(description)) => Some((name, description)),
+            _ => {
+                return Err(anyhow!(
+                    "--name and --description are required to propose closing buffers of {}",
+                    args.authority
+                ))
+            }
+        }
+    };
 
-    for buffer in buffers.iter().take(BATCH_SIZE) {
-        instructions.push(bpf_loader_upgradeable::close_any(
-            &buffer.address,
-            &args.recipient,
-            Some(&args.authority),
-            None,
-        ));
-    }
+    let mut closed = vec![];
+    let mut reclaimed_lamports = 0;
 
-    let instructions = instructions
-        .into_iter()
-        .map(into_instruction_data)
+    let instructions = own_buffers
+        .iter()
+        .map(|buffer| {
+            bpf_loader_upgradeable::close_any(&buffer.address, &args.recipient, Some(&signer), None)
+        })
         .collect();
 
-    let create_ix = create_proposal(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.governance_id,
-        &proposal_owner_record,
-        &config.keypair.pubkey(),
-        &config.keypair.pubkey(),
-        None,
-        &config.realm_id,
-        args.name,
-        args.description,
-        &governing_token_mint,
-        args.vote_type,
-        args.options,
-        true,
-        proposal_index,
-    );
+    let mut own_buffers = own_buffers.iter();
+    for mut tx in pack_instructions(instructions, &signer)? {
+        tx.try_sign(&[&config.keypair], config.client.get_latest_blockhash()?)?;
+        let signature = send_transaction(&config, &tx)?;
 
-    let proposal_address = get_proposal_address(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.governance_id,
-        &governing_token_mint,
-        &proposal_index.to_le_bytes(),
-    );
+        for buffer in own_buffers.by_ref().take(tx.message.instructions.len()) {
+            closed.push(buffer.address);
+            reclaimed_lamports += buffer.lamports;
+        }
 
-    let token_owner_record = get_token_owner_record_address(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.realm_id,
-        &governing_token_mint,
-        &config.keypair.pubkey(),
-    );
+        debug!(
+            "Closed {} buffer(s): {}",
+            closed.len(),
+            format_signature(&signature)
+        );
+    }
 
-    let add_signatory_ix = add_signatory(
-        &GOVERNANCE_PROGRAM_ID,
-        &proposal_address,
-        &token_owner_record,
-        &config.keypair.pubkey(),
-        &config.keypair.pubkey(),
-        &config.keypair.pubkey(),
-    );
+    let (name, description) = match proposal_text {
+        Some(proposal_text) => proposal_text,
+        None => {
+            return Ok(BuffersClosed {
+                dry_run: config.dry_run,
+                closed,
+                reclaimed_lamports,
+                proposed: vec![],
+                proposed_lamports: 0,
+                proposal: None,
+                skipped,
+            })
+        }
+    };
 
-    // Empirically determined from existing proposals. Not sure the significance of these yet.
-    let option_index = 0;
-    let index = 0;
-    let hold_up_time = 0;
-
-    let insert_ix = insert_transaction(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.governance_id,
-        &proposal_address,
-        &token_owner_record,
-        &config.keypair.pubkey(),
-        &config.keypair.pubkey(),
-        option_index,
-        index,
-        hold_up_time,
-        instructions,
-    );
+    let instructions = governance_buffers
+        .iter()
+        .map(|buffer| {
+            into_instruction_data(bpf_loader_upgradeable::close_any(
+                &buffer.address,
+                &args.recipient,
+                Some(&args.authority),
+                None,
+            ))
+        })
+        .collect();
 
-    let sign_off_ix = sign_off_proposal(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.realm_id,
-        &config.governance_id,
-        &proposal_address,
-        &config.keypair.pubkey(),
-        None,
-    );
+    // Closing buffers is independent, so they are spread over as many proposal transactions
+    // as needed for each one to fit in a single Solana transaction.
+    let transactions = ProposalTransaction::split(0, instructions)?;
 
-    let tx = solana_sdk::transaction::Transaction::new_signed_with_payer(
-        &[create_ix, add_signatory_ix, insert_ix, sign_off_ix],
-        Some(&config.keypair.pubkey()),
-        &[&config.keypair],
-        config.client.get_latest_blockhash()?,
+    debug!(
+        "Closing {} buffer(s) in {} proposal transaction(s)",
+        governance_buffers.len(),
+        transactions.len()
     );
 
-    config
-        .client
-        .send_and_confirm_transaction_with_spinner(&tx)?;
+    let proposal = ProposalBuilder::new()
+        .name(name)
+        .description(description)
+        .mint_type(config.mint_type(args.mint_type))
+        .choice_type(args.choice_type)
+        .options(args.options)
+        .resume(args.resume)
+        .transactions(transactions)
+        .build(&config)?;
 
-    Ok(())
+    Ok(BuffersClosed {
+        dry_run: config.dry_run,
+        closed,
+        reclaimed_lamports,
+        proposed: governance_buffers
+            .iter()
+            .map(|buffer| buffer.address)
+            .collect(),
+        proposed_lamports: governance_buffers
+            .iter()
+            .map(|buffer| buffer.lamports)
+            .sum(),
+        proposal: Some(proposal.send(&config)?),
+        skipped,
+    })
 }
 
-fn into_instruction_data(instruction: Instruction) -> InstructionData {
-    let accounts = instruction
-        .accounts
-        .into_iter()
-        .map(into_account_meta_data)
-        .collect();
+/// Maps every buffer that an upgrade in a proposal still in progress uses as its source to
+/// that proposal and its state. Scanning the proposals is skipped when there are no candidates.
+fn get_protected_buffers(
+    config: &config::CliConfig,
+    candidates: &[UpgradeableBuffer],
+) -> Result<HashMap<Pubkey, (Pubkey, ProposalState)>> {
+    if candidates.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let proposals = get_governance_proposals(config, &[MintType::Member, MintType::Council])?;
+
+    let mut protected = HashMap::new();
+    for (_, _, address, proposal) in proposals {
+        if !matches!(
+            proposal.state,
+            ProposalState::Draft
+                | ProposalState::SigningOff
+                | ProposalState::Voting
+                | ProposalState::Succeeded
+                | ProposalState::Executing
+                | ProposalState::ExecutingWithErrors
+        ) {
+            continue;
+        }
 
-    InstructionData {
-        program_id: instruction.program_id,
-        accounts,
-        data: instruction.data,
+        for (_, transaction) in get_proposal_transactions(&config.client, &address)? {
+            if transaction.execution_status == TransactionExecutionStatus::Success {
+                continue;
+            }
+
+            for instruction in &transaction.instructions {
+                if instruction.program_id != bpf_loader_upgradeable::id() {
+                    continue;
+                }
+                if let Ok(UpgradeableLoaderInstruction::Upgrade) =
+                    limited_deserialize(&instruction.data, PACKET_DATA_SIZE as u64)
+                {
+                    if let Some(buffer) = instruction.accounts.get(2) {
+                        protected.insert(buffer.pubkey, (address, proposal.state.clone()));
+                    }
+                }
+            }
+        }
     }
+
+    Ok(protected)
 }
 
-fn into_account_meta_data(account: AccountMeta) -> AccountMetaData {
-    AccountMetaData {
-        pubkey: account.pubkey,
-        is_signer: account.is_signer,
-        is_writable: account.is_writable,
-    }
+fn serialize_pubkeys<S>(pubkeys: &[Pubkey], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.collect_seq(pubkeys.iter().map(|pubkey| pubkey.to_string()))
 }
@@ -0,0 +1,56 @@
+use solana_sdk::native_token::lamports_to_sol;
+
+use super::*;
+
+pub struct CloseProgramArgs {
+    pub global: GlobalArgs,
+    pub program_id: Option<Pubkey>,
+    pub recipient: Option<Pubkey>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub mint_type: Option<MintType>,
+    pub yes: bool,
+}
+
+pub fn close_program(args: CloseProgramArgs) -> Result<ProposalCreated> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let program = get_governed_program(&config, args.program_id)?;
+    let program_id = program.program_id;
+
+    // Keeps the rent in the DAO unless told otherwise.
+    let recipient = args.recipient.unwrap_or_else(|| {
+        get_native_treasury_address(&GOVERNANCE_PROGRAM_ID, &config.governance_id)
+    });
+
+    confirm(
+        &config,
+        &format!(
+            "Once executed, program {program_id} stops working and its id can never be \
+             deployed to again. {} SOL of ProgramData rent goes to {recipient}.",
+            lamports_to_sol(program.lamports)
+        ),
+        args.yes,
+    )?;
+
+    let instruction = into_instruction_data(bpf_loader_upgradeable::close_any(
+        &program.program_data,
+        &recipient,
+        Some(&config.governance_id),
+        Some(&program_id),
+    ));
+
+    let proposal = ProposalBuilder::new()
+        .name(
+            args.name
+                .unwrap_or_else(|| format!("Close program {program_id}")),
+        )
+        .description(args.description.unwrap_or_else(|| {
+            format!("Close program {program_id} and send its ProgramData rent to {recipient}")
+        }))
+        .mint_type(config.mint_type(args.mint_type))
+        .transaction(ProposalTransaction::new(0, vec![instruction]))
+        .build(&config)?;
+
+    proposal.send(&config)
+}
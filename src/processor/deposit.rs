@@ -1,24 +1,19 @@
 use super::*;
 
 pub struct DepositArgs {
-    pub keypair_path: Option<PathBuf>,
-    pub rpc_url: Option<String>,
+    pub global: GlobalArgs,
     pub amount: u64,
-    pub mint_type: MintType,
+    pub mint_type: Option<MintType>,
 }
 
-pub fn deposit(args: DepositArgs) -> Result<()> {
-    let config = config::CliConfig::new(args.keypair_path, args.rpc_url)?;
+pub fn deposit(args: DepositArgs) -> Result<SignatureOutput> {
+    let config = config::CliConfig::new(&args.global)?;
 
     let realm: RealmV2 = get_governance_state(&config.client, &config.realm_id)?;
 
-    let governing_token_mint = match args.mint_type {
-        MintType::Member => realm.community_mint,
-        MintType::Council => realm
-            .config
-            .council_mint
-            .ok_or_else(|| anyhow!("Council mint not found"))?,
-    };
+    let governing_token_mint = config
+        .mint_type(args.mint_type)
+        .governing_token_mint(&realm)?;
 
     debug!("Governing Token Mint: {governing_token_mint}");
 
@@ -47,9 +42,7 @@ pub fn deposit(args: DepositArgs) -> Result<()> {
         config.client.get_latest_blockhash()?,
     );
 
-    config
-        .client
-        .send_and_confirm_transaction_with_spinner(&tx)?;
+    let signature = send_transaction(&config, &tx)?;
 
-    Ok(())
+    Ok(SignatureOutput { signature })
 }
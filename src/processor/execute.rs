@@ -1,124 +1,201 @@
 use super::*;
 
 pub struct ExecuteArgs {
-    pub keypair_path: Option<PathBuf>,
-    pub rpc_url: Option<String>,
+    pub global: GlobalArgs,
     pub proposal_id: Option<Pubkey>,
     pub latest: bool,
-    pub mint_type: MintType,
+    pub mint_type: Option<MintType>,
 }
 
-pub fn execute(args: ExecuteArgs) -> Result<()> {
-    let config = config::CliConfig::new(args.keypair_path, args.rpc_url)?;
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutedTransaction {
+    pub option_index: u8,
+    pub transaction_index: u16,
+    #[serde(with = "option_signature")]
+    pub signature: Option<Signature>,
+}
 
-    let realm: RealmV2 = get_governance_state(&config.client, &config.realm_id)?;
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeldUpTransaction {
+    pub option_index: u8,
+    pub transaction_index: u16,
+    pub remaining_seconds: i64,
+}
 
-    let governing_token_mint = match args.mint_type {
-        MintType::Member => realm.community_mint,
-        MintType::Council => realm
-            .config
-            .council_mint
-            .ok_or_else(|| anyhow!("Council mint not found"))?,
-    };
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteOutput {
+    #[serde(with = "display_fromstr")]
+    pub proposal: Pubkey,
+    pub executed: Vec<ExecutedTransaction>,
+    pub held_up: Vec<HeldUpTransaction>,
+}
 
-    debug!("Governing token mint: {governing_token_mint}");
+impl fmt::Display for ExecuteOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for transaction in &self.held_up {
+            writeln!(
+                f,
+                "Transaction [{}, {}] is held up for another {}s",
+                transaction.option_index,
+                transaction.transaction_index,
+                transaction.remaining_seconds
+            )?;
+        }
+        for transaction in &self.executed {
+            writeln!(
+                f,
+                "Executed transaction [{}, {}]: {}",
+                transaction.option_index,
+                transaction.transaction_index,
+                format_signature(&transaction.signature)
+            )?;
+        }
+        write!(
+            f,
+            "Executed {} transaction(s), {} still held up",
+            self.executed.len(),
+            self.held_up.len()
+        )
+    }
+}
 
-    let proposal_id = if args.latest {
-        let governance: GovernanceV2 = get_governance_state(&config.client, &config.governance_id)?;
-        let proposal_index = governance.proposals_count - 1;
+pub fn execute(args: ExecuteArgs) -> Result<ExecuteOutput> {
+    let config = config::CliConfig::new(&args.global)?;
 
-        get_proposal_address(
-            &GOVERNANCE_PROGRAM_ID,
-            &config.governance_id,
-            &governing_token_mint,
-            &proposal_index.to_le_bytes(),
-        )
-    } else if let Some(proposal_id) = args.proposal_id {
-        proposal_id
-    } else {
-        return Err(anyhow!("Either --latest or --proposal-id must be provided"));
-    };
+    let proposal_id = resolve_proposal_id(
+        &config,
+        &config.mint_type(args.mint_type),
+        args.proposal_id,
+        args.latest,
+    )?;
 
     debug!("Proposal ID: {proposal_id}");
 
-    // We need to find the owner of the proposal to find the correct proposal_owner_record
-    // as this will only be the voter if the voter also created the proposal.
-
     let proposal: ProposalV2 = get_governance_state(&config.client, &proposal_id)?;
 
-    // All our upgrade proposals should have option and instruction indexes of 0,
-    // but to support more generically we should get the indexes.
-
-    let option_index = (proposal
-        .options
-        .first()
-        .expect("More than one proposal option found!")
-        .transactions_next_index
-        - 1) as u8;
-
-    debug!("Option index: {option_index}");
-
-    let proposal_transaction_pubkey = get_proposal_transaction_address(
-        &GOVERNANCE_PROGRAM_ID,
-        &proposal_id,
-        &option_index.to_le_bytes(),
-        &[0, 0],
-    );
-
-    debug!("Proposal transaction pubkey: {proposal_transaction_pubkey}");
-
-    let proposal_transaction: ProposalTransactionV2 =
-        get_governance_state(&config.client, &proposal_transaction_pubkey)?;
+    match proposal.state {
+        ProposalState::Succeeded
+        | ProposalState::Executing
+        | ProposalState::ExecutingWithErrors => {}
+        state => {
+            return Err(anyhow!(
+                "Proposal {proposal_id} is {:?} and cannot be executed",
+                state
+            ))
+        }
+    }
 
-    if proposal_transaction.instructions.len() > 1 {
-        return Err(anyhow!(
-            "More than one instruction found in proposal transaction"
-        ));
+    let voting_completed_at = proposal
+        .voting_completed_at
+        .ok_or_else(|| anyhow!("Proposal {proposal_id} has no voting completion time"))?;
+    let now = get_unix_timestamp(&config.client)?;
+
+    debug!("Voting completed at: {voting_completed_at}, now: {now}");
+
+    // Instructions are signed by the governance or its native treasury via CPI, so neither can be
+    // a signer on the outer transaction.
+    let native_treasury =
+        get_native_treasury_address(&GOVERNANCE_PROGRAM_ID, &config.governance_id);
+
+    let mut executed = vec![];
+    let mut held_up = vec![];
+
+    for (option_index, option) in proposal.options.iter().enumerate() {
+        if option.vote_result != OptionVoteResult::Succeeded {
+            debug!("Skipping option {option_index}: {:?}", option.vote_result);
+            continue;
+        }
+
+        let option_index = option_index as u8;
+
+        for (transaction_index, proposal_transaction_pubkey, proposal_transaction) in
+            get_option_transactions(
+                &config.client,
+                &proposal_id,
+                option_index,
+                option.transactions_next_index,
+            )?
+        {
+            debug!(
+                "Proposal transaction [{option_index}, {transaction_index}]: {proposal_transaction_pubkey}"
+            );
+
+            if proposal_transaction.execution_status == TransactionExecutionStatus::Success {
+                debug!("Already executed: {proposal_transaction_pubkey}");
+                continue;
+            }
+
+            let executable_at = voting_completed_at + proposal_transaction.hold_up_time as i64;
+            if executable_at > now {
+                held_up.push(HeldUpTransaction {
+                    option_index,
+                    transaction_index,
+                    remaining_seconds: executable_at - now,
+                });
+                continue;
+            }
+
+            let instruction_program_id = proposal_transaction
+                .instructions
+                .first()
+                .ok_or_else(|| anyhow!("No instructions found in proposal transaction"))?
+                .program_id;
+
+            // The governance program passes every account to every instruction, so the
+            // program ids of the remaining instructions are passed as plain accounts.
+            let mut instruction_accounts: Vec<AccountMeta> = vec![];
+            for (i, instruction) in proposal_transaction.instructions.iter().enumerate() {
+                if i > 0 {
+                    instruction_accounts
+                        .push(AccountMeta::new_readonly(instruction.program_id, false));
+                }
+                instruction_accounts.extend(instruction.accounts.iter().map(|a| AccountMeta {
+                    pubkey: a.pubkey,
+                    is_signer: if a.pubkey == config.governance_id || a.pubkey == native_treasury {
+                        false
+                    } else {
+                        a.is_signer
+                    },
+                    is_writable: a.is_writable,
+                }));
+            }
+
+            let ix = execute_transaction(
+                &GOVERNANCE_PROGRAM_ID,
+                &config.governance_id,
+                &proposal_id,
+                &proposal_transaction_pubkey,
+                &instruction_program_id,
+                &instruction_accounts,
+            );
+
+            let tx = solana_sdk::transaction::Transaction::new_signed_with_payer(
+                &[ix],
+                Some(&config.keypair.pubkey()),
+                &[&config.keypair],
+                config.client.get_latest_blockhash()?,
+            );
+
+            let signature = send_transaction(&config, &tx)?;
+
+            debug!(
+                "Executed transaction [{option_index}, {transaction_index}]: {}",
+                format_signature(&signature)
+            );
+            executed.push(ExecutedTransaction {
+                option_index,
+                transaction_index,
+                signature,
+            });
+        }
     }
-    let instruction = proposal_transaction
-        .instructions
-        .first()
-        .expect("No instructions found in proposal transaction");
-
-    let instruction_program_id = instruction.program_id;
-
-    // Convert from the SPL governance type to the Solana SDK type
-    // Manually set the signer to false for the governance keypair since that gets
-    // signed via CPI by the governance program.
-    let instruction_accounts: Vec<AccountMeta> = instruction
-        .accounts
-        .clone()
-        .into_iter()
-        .map(|a| AccountMeta {
-            pubkey: a.pubkey,
-            is_signer: if a.pubkey == config.governance_id {
-                false
-            } else {
-                a.is_signer
-            },
-            is_writable: a.is_writable,
-        })
-        .collect();
-
-    let ix = execute_transaction(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.governance_id,
-        &proposal_id,
-        &proposal_transaction_pubkey,
-        &instruction_program_id,
-        &instruction_accounts,
-    );
-
-    let tx = solana_sdk::transaction::Transaction::new_signed_with_payer(
-        &[ix],
-        Some(&config.keypair.pubkey()),
-        &[&config.keypair],
-        config.client.get_latest_blockhash()?,
-    );
-
-    config
-        .client
-        .send_and_confirm_transaction_with_spinner(&tx)?;
-
-    Ok(())
+
+    Ok(ExecuteOutput {
+        proposal: proposal_id,
+        executed,
+        held_up,
+    })
 }
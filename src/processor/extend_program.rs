@@ -0,0 +1,50 @@
+use super::*;
+
+pub struct ExtendProgramArgs {
+    pub global: GlobalArgs,
+    pub program_id: Option<Pubkey>,
+    pub bytes: usize,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub mint_type: Option<MintType>,
+}
+
+pub fn extend_program(args: ExtendProgramArgs) -> Result<ProposalCreated> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    if args.bytes == 0 {
+        return Err(anyhow!("--bytes must be greater than zero"));
+    }
+
+    let program = get_governed_program(&config, args.program_id)?;
+    let program_id = program.program_id;
+
+    let new_len = program
+        .data_len
+        .checked_add(args.bytes)
+        .ok_or_else(|| anyhow!("--bytes {} is too large", args.bytes))?;
+
+    let instructions = create_extend_program_instructions(&config, &program, args.bytes)?;
+
+    let proposal = ProposalBuilder::new()
+        .name(
+            args.name
+                .unwrap_or_else(|| format!("Extend program {program_id}")),
+        )
+        .description(args.description.unwrap_or_else(|| {
+            format!(
+                "Extend ProgramData {} of program {program_id} by {} bytes to {} bytes",
+                program.program_data, args.bytes, new_len
+            )
+        }))
+        .mint_type(config.mint_type(args.mint_type))
+        .transactions(
+            instructions
+                .into_iter()
+                .map(|instruction| ProposalTransaction::new(0, vec![instruction]))
+                .collect(),
+        )
+        .build(&config)?;
+
+    proposal.send(&config)
+}
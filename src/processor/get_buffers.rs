@@ -1,39 +1,95 @@
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_sdk::{clock::Slot, native_token::lamports_to_sol};
+
 use super::*;
 
-#[derive(Debug, Clone)]
-pub struct GetBuffersArgs {
-    pub keypair_path: Option<PathBuf>,
-    pub rpc_url: Option<String>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeableBuffer {
+    #[serde(with = "display_fromstr")]
+    pub address: Pubkey,
+    #[serde(with = "display_fromstr")]
+    pub authority: Pubkey,
+    /// Size of the buffer account, 0 when only its metadata was fetched by `get_buffers`.
+    pub data_len: usize,
+    pub lamports: u64,
+    /// Hash of the executable in the buffer, `None` when only its metadata was fetched.
+    #[serde(skip)]
+    pub hash: Option<String>,
+}
+
+pub struct BufferReportArgs {
+    pub global: GlobalArgs,
     pub authority: Pubkey,
+    pub min_age: Option<i64>,
+    pub min_size: Option<usize>,
+    pub sort: BufferSort,
+}
+
+/// Order of the buffer report, oldest or largest first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum BufferSort {
+    #[default]
+    Age,
+    Size,
+    Lamports,
 }
 
-impl From<CloseBuffersArgs> for GetBuffersArgs {
-    fn from(args: CloseBuffersArgs) -> Self {
-        Self {
-            keypair_path: args.keypair_path,
-            rpc_url: args.rpc_url,
-            authority: args.authority,
+impl FromStr for BufferSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "age" => Ok(BufferSort::Age),
+            "size" => Ok(BufferSort::Size),
+            "lamports" | "rent" => Ok(BufferSort::Lamports),
+            _ => Err(anyhow!("Invalid sort, expected age, size or lamports")),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct UpgradeableBuffer {
-    pub address: Pubkey,
-    pub authority: Pubkey,
-    pub data_len: usize,
-    pub lamports: u64,
+pub struct BufferInfo {
+    #[serde(flatten)]
+    pub buffer: UpgradeableBuffer,
+    /// Slot of the last transaction that touched the buffer, usually its last write.
+    pub slot: Option<Slot>,
+    pub age_seconds: Option<i64>,
 }
 
-const ACCOUNT_TYPE_SIZE: usize = 4;
-const SLOT_SIZE: usize = size_of::<u64>();
-const OPTION_SIZE: usize = 1;
-const PUBKEY_LEN: usize = 32;
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferReport {
+    pub buffers: Vec<BufferInfo>,
+    pub total_lamports: u64,
+}
 
-pub fn get_buffers(args: GetBuffersArgs) -> Result<Vec<UpgradeableBuffer>> {
-    let config = config::CliConfig::new(args.keypair_path, args.rpc_url)?;
+impl fmt::Display for BufferReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for info in &self.buffers {
+            let buffer = &info.buffer;
+            writeln!(
+                f,
+                "{}  {:>8} bytes  {:>12} SOL  slot {:<10} {}",
+                buffer.address,
+                buffer.data_len,
+                lamports_to_sol(buffer.lamports),
+                info.slot.map_or("-".to_string(), |slot| slot.to_string()),
+                info.age_seconds.map_or("-".to_string(), format_age)
+            )?;
+        }
+        write!(
+            f,
+            "{} buffer(s), {} SOL reclaimable",
+            self.buffers.len(),
+            lamports_to_sol(self.total_lamports)
+        )
+    }
+}
 
+/// Lists the buffers of an authority from their metadata only, leaving `data_len` at 0.
+pub fn get_buffers(client: &RpcClient, authority: &Pubkey) -> Result<Vec<UpgradeableBuffer>> {
     let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
         0,
         &[1, 0, 0, 0],
@@ -44,12 +100,12 @@ pub fn get_buffers(args: GetBuffersArgs) -> Result<Vec<UpgradeableBuffer>> {
     )));
     filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
         ACCOUNT_TYPE_SIZE + OPTION_SIZE,
-        args.authority.as_ref(),
+        authority.as_ref(),
     )));
 
     let length = ACCOUNT_TYPE_SIZE + SLOT_SIZE + OPTION_SIZE + PUBKEY_LEN;
 
-    let results = config.client.get_program_accounts_with_config(
+    let results = client.get_program_accounts_with_config(
         &bpf_loader_upgradeable::id(),
         RpcProgramAccountsConfig {
             filters: Some(filters),
@@ -73,12 +129,189 @@ pub fn get_buffers(args: GetBuffersArgs) -> Result<Vec<UpgradeableBuffer>> {
             buffers.push(UpgradeableBuffer {
                 address,
                 authority: authority_address.unwrap(),
-                data_len: account.data.len(),
+                data_len: 0,
                 lamports: account.lamports,
+                hash: None,
             });
         } else {
             return Err(anyhow!("Error parsing Buffer account {}", address));
         }
     }
+
     Ok(buffers)
 }
+
+pub fn buffer_report(args: BufferReportArgs) -> Result<BufferReport> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let buffers = get_buffers(&config.client, &args.authority)?;
+    let buffers = get_buffer_sizes(&config.client, buffers)?;
+
+    let now = get_unix_timestamp(&config.client)?;
+
+    let mut report = vec![];
+    for buffer in buffers {
+        if args
+            .min_size
+            .is_some_and(|min_size| buffer.data_len < min_size)
+        {
+            continue;
+        }
+
+        let last_signature = config
+            .client
+            .get_signatures_for_address_with_config(
+                &buffer.address,
+                GetConfirmedSignaturesForAddress2Config {
+                    limit: Some(1),
+                    ..GetConfirmedSignaturesForAddress2Config::default()
+                },
+            )?
+            .into_iter()
+            .next();
+
+        let slot = last_signature.as_ref().map(|signature| signature.slot);
+        let age_seconds = last_signature
+            .and_then(|signature| signature.block_time)
+            .map(|block_time| now - block_time);
+
+        // Buffers of unknown age are kept so they can't be mistaken for recent ones.
+        if let (Some(min_age), Some(age_seconds)) = (args.min_age, age_seconds) {
+            if age_seconds < min_age {
+                continue;
+            }
+        }
+
+        report.push(BufferInfo {
+            buffer,
+            slot,
+            age_seconds,
+        });
+    }
+
+    match args.sort {
+        BufferSort::Age => report.sort_by_key(|info| std::cmp::Reverse(info.age_seconds)),
+        BufferSort::Size => report.sort_by_key(|info| std::cmp::Reverse(info.buffer.data_len)),
+        BufferSort::Lamports => report.sort_by_key(|info| std::cmp::Reverse(info.buffer.lamports)),
+    }
+
+    Ok(BufferReport {
+        total_lamports: report.iter().map(|info| info.buffer.lamports).sum(),
+        buffers: report,
+    })
+}
+
+/// Fills in the size of each buffer, which the metadata `get_buffers` fetches doesn't include.
+/// Buffers closed since they were listed are left out.
+fn get_buffer_sizes(
+    client: &RpcClient,
+    buffers: Vec<UpgradeableBuffer>,
+) -> Result<Vec<UpgradeableBuffer>> {
+    let mut sized = vec![];
+
+    // Compression keeps the mostly zero padded buffers small.
+    for chunk in buffers.chunks(FULL_FETCH_BATCH_SIZE) {
+        let addresses: Vec<Pubkey> = chunk.iter().map(|buffer| buffer.address).collect();
+        let accounts = client
+            .get_multiple_accounts_with_config(
+                &addresses,
+                RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64Zstd),
+                    ..RpcAccountInfoConfig::default()
+                },
+            )?
+            .value;
+
+        for (buffer, account) in chunk.iter().zip(accounts) {
+            match account {
+                Some(account) => sized.push(UpgradeableBuffer {
+                    data_len: account.data.len(),
+                    hash: Some(buffer_data_hash(&account.data)),
+                    ..buffer.clone()
+                }),
+                None => eprintln!("Buffer {} was closed while listing it", buffer.address),
+            }
+        }
+    }
+
+    Ok(sized)
+}
+
+/// Parses an age such as `90s`, `45m`, `12h` or `30d` into seconds.
+pub fn parse_age(s: &str) -> Result<i64> {
+    let (value, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let value: i64 = value.parse().map_err(|_| anyhow!("Invalid age {s}"))?;
+
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(anyhow!(
+                "Invalid age {s}, expected a number of s, m, h or d"
+            ))
+        }
+    };
+
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| anyhow!("Invalid age {s}, too large"))
+}
+
+fn format_age(seconds: i64) -> String {
+    match seconds {
+        s if s >= 24 * 60 * 60 => format!("{}d", s / (24 * 60 * 60)),
+        s if s >= 60 * 60 => format!("{}h", s / (60 * 60)),
+        s if s >= 60 => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ages_with_units() {
+        assert_eq!(parse_age("90s").unwrap(), 90);
+        assert_eq!(parse_age("45m").unwrap(), 45 * 60);
+        assert_eq!(parse_age("12h").unwrap(), 12 * 60 * 60);
+        assert_eq!(parse_age("30d").unwrap(), 30 * 24 * 60 * 60);
+    }
+
+    #[test]
+    fn parses_a_bare_number_as_seconds() {
+        assert_eq!(parse_age("120").unwrap(), 120);
+        assert_eq!(parse_age("0").unwrap(), 0);
+        assert_eq!(parse_age("0d").unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_invalid_ages() {
+        for age in ["", "d", "10w", "10 d", "1.5h", "-5m", "10dd"] {
+            assert!(parse_age(age).is_err(), "{age}");
+        }
+    }
+
+    #[test]
+    fn rejects_ages_that_overflow() {
+        assert!(parse_age("99999999999999999999").is_err());
+        assert!(parse_age(&format!("{}d", i64::MAX / 1000)).is_err());
+        assert_eq!(parse_age(&format!("{}s", i64::MAX)).unwrap(), i64::MAX);
+    }
+
+    #[test]
+    fn formats_ages_in_the_largest_whole_unit() {
+        assert_eq!(format_age(0), "0s");
+        assert_eq!(format_age(59), "59s");
+        assert_eq!(format_age(60), "1m");
+        assert_eq!(format_age(60 * 60 - 1), "59m");
+        assert_eq!(format_age(60 * 60), "1h");
+        assert_eq!(format_age(24 * 60 * 60 - 1), "23h");
+        assert_eq!(format_age(3 * 24 * 60 * 60 + 5), "3d");
+    }
+}
@@ -1,17 +1,91 @@
+use spl_governance::state::{
+    enums::{VoteThresholdPercentage, VoteTipping},
+    governance::GovernanceConfig,
+};
+
 use super::*;
 
 pub struct GetGovConfigArgs {
-    pub keypair_path: Option<PathBuf>,
-    pub rpc_url: Option<String>,
+    pub global: GlobalArgs,
 }
 
-pub fn get_gov_config(args: GetGovConfigArgs) -> Result<()> {
-    let config = config::CliConfig::new(args.keypair_path, args.rpc_url)?;
+/// Serializable mirror of the SPL `GovernanceConfig`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GovConfigOutput {
+    #[serde(with = "display_fromstr")]
+    pub governance: Pubkey,
+    pub vote_threshold_percentage: String,
+    pub min_community_weight_to_create_proposal: u64,
+    pub min_transaction_hold_up_time: u32,
+    pub max_voting_time: u32,
+    pub vote_tipping: String,
+    pub proposal_cool_off_time: u32,
+    pub min_council_weight_to_create_proposal: u64,
+}
 
-    let governance = get_governance_data(&config.client, &config.governance_id)?;
-    let governance_config = governance.config;
+impl GovConfigOutput {
+    fn new(governance: Pubkey, config: GovernanceConfig) -> Self {
+        Self {
+            governance,
+            vote_threshold_percentage: match config.vote_threshold_percentage {
+                VoteThresholdPercentage::YesVote(percentage) => format!("yes:{percentage}"),
+                VoteThresholdPercentage::Quorum(percentage) => format!("quorum:{percentage}"),
+            },
+            min_community_weight_to_create_proposal: config.min_community_weight_to_create_proposal,
+            min_transaction_hold_up_time: config.min_transaction_hold_up_time,
+            max_voting_time: config.max_voting_time,
+            vote_tipping: match config.vote_tipping {
+                VoteTipping::Strict => "strict".to_string(),
+                VoteTipping::Early => "early".to_string(),
+                VoteTipping::Disabled => "disabled".to_string(),
+            },
+            proposal_cool_off_time: config.proposal_cool_off_time,
+            min_council_weight_to_create_proposal: config.min_council_weight_to_create_proposal,
+        }
+    }
+}
 
-    println!("Current Governance Config: {:#?}", governance_config);
+impl fmt::Display for GovConfigOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Governance: {}", self.governance)?;
+        writeln!(
+            f,
+            "Vote threshold percentage: {}",
+            self.vote_threshold_percentage
+        )?;
+        writeln!(
+            f,
+            "Min community weight to create proposal: {}",
+            self.min_community_weight_to_create_proposal
+        )?;
+        writeln!(
+            f,
+            "Min council weight to create proposal: {}",
+            self.min_council_weight_to_create_proposal
+        )?;
+        writeln!(
+            f,
+            "Min transaction hold up time: {}s",
+            self.min_transaction_hold_up_time
+        )?;
+        writeln!(f, "Max voting time: {}s", self.max_voting_time)?;
+        writeln!(f, "Vote tipping: {}", self.vote_tipping)?;
+        write!(
+            f,
+            "Proposal cool off time: {}s",
+            self.proposal_cool_off_time
+        )
+    }
+}
+
+pub fn get_gov_config(args: GetGovConfigArgs) -> Result<GovConfigOutput> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let governance = get_governance_data(&config.client, &config.governance_id)?;
 
-    Ok(())
+    Ok(GovConfigOutput::new(
+        config.governance_id,
+        governance.config,
+    ))
 }
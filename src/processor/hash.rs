@@ -0,0 +1,112 @@
+use solana_program::hash::hash;
+
+use super::*;
+
+pub struct BufferHashArgs {
+    pub global: GlobalArgs,
+    pub buffer: Pubkey,
+}
+
+pub struct ProgramHashArgs {
+    pub global: GlobalArgs,
+    pub program_id: Pubkey,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutableHash {
+    #[serde(with = "display_fromstr")]
+    pub address: Pubkey,
+    pub hash: String,
+}
+
+impl fmt::Display for ExecutableHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.hash)
+    }
+}
+
+pub fn buffer_hash(args: BufferHashArgs) -> Result<ExecutableHash> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let buffer = get_upgradeable_buffer(&config.client, &args.buffer)?;
+
+    Ok(ExecutableHash {
+        address: args.buffer,
+        hash: buffer
+            .hash
+            .expect("get_upgradeable_buffer fetches the whole buffer"),
+    })
+}
+
+pub fn program_hash(args: ProgramHashArgs) -> Result<ExecutableHash> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let program = get_upgradeable_program(&config.client, &args.program_id)?;
+
+    Ok(ExecutableHash {
+        address: args.program_id,
+        hash: program.hash,
+    })
+}
+
+/// Hash of the executable held in a ProgramData account's data.
+pub fn program_data_hash(data: &[u8]) -> String {
+    executable_hash(&data[UpgradeableLoaderState::size_of_programdata_metadata()..])
+}
+
+/// Hash of the executable held in a Buffer account's data.
+pub fn buffer_data_hash(data: &[u8]) -> String {
+    executable_hash(&data[UpgradeableLoaderState::size_of_buffer_metadata()..])
+}
+
+/// SHA-256 of the executable without the zero padding the loader leaves after it, as hex, the
+/// same as `solana-verify get-program-hash`.
+fn executable_hash(data: &[u8]) -> String {
+    let len = data
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |i| i + 1);
+
+    hash(&data[..len])
+        .to_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_HASH: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    const EMPTY_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[test]
+    fn hashes_the_executable_as_sha256_hex() {
+        assert_eq!(executable_hash(b"abc"), ABC_HASH);
+    }
+
+    #[test]
+    fn strips_trailing_zero_padding() {
+        assert_eq!(executable_hash(b"abc\0\0\0\0"), ABC_HASH);
+        assert_eq!(executable_hash(&[0; 16]), EMPTY_HASH);
+        assert_eq!(executable_hash(&[]), EMPTY_HASH);
+    }
+
+    #[test]
+    fn keeps_zero_bytes_inside_the_executable() {
+        assert_ne!(executable_hash(b"ab\0c"), executable_hash(b"abc"));
+    }
+
+    #[test]
+    fn skips_the_account_metadata() {
+        let mut program_data = vec![0xff; UpgradeableLoaderState::size_of_programdata_metadata()];
+        program_data.extend_from_slice(b"abc\0\0");
+        assert_eq!(program_data_hash(&program_data), ABC_HASH);
+
+        let mut buffer = vec![0xff; UpgradeableLoaderState::size_of_buffer_metadata()];
+        buffer.extend_from_slice(b"abc\0\0");
+        assert_eq!(buffer_data_hash(&buffer), ABC_HASH);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use solana_sdk::clock::Slot;
+
+use super::*;
+
+pub struct ListProgramsArgs {
+    pub global: GlobalArgs,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramSummary {
+    #[serde(with = "display_fromstr")]
+    pub program_id: Pubkey,
+    #[serde(with = "display_fromstr")]
+    pub program_data: Pubkey,
+    /// Slot the program was last deployed or upgraded in.
+    pub slot: Slot,
+    /// Size of the ProgramData account, including its metadata.
+    pub data_len: usize,
+    pub hash: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct ProgramList(pub Vec<ProgramSummary>);
+
+impl fmt::Display for ProgramList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for program in &self.0 {
+            writeln!(
+                f,
+                "{}  slot {:<10} {:>8} bytes  {}",
+                program.program_id, program.slot, program.data_len, program.hash
+            )?;
+        }
+        write!(f, "{} program(s)", self.0.len())
+    }
+}
+
+pub fn list_programs(args: ListProgramsArgs) -> Result<ProgramList> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let filters = vec![
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &[3, 0, 0, 0])),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            ACCOUNT_TYPE_SIZE + SLOT_SIZE,
+            &[1],
+        )),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            ACCOUNT_TYPE_SIZE + SLOT_SIZE + OPTION_SIZE,
+            config.governance_id.as_ref(),
+        )),
+    ];
+
+    let length = ACCOUNT_TYPE_SIZE + SLOT_SIZE + OPTION_SIZE + PUBKEY_LEN;
+
+    let results = config.client.get_program_accounts_with_config(
+        &bpf_loader_upgradeable::id(),
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig { offset: 0, length }),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    let mut program_datas = vec![];
+    for (address, account) in results.into_iter() {
+        match account.state() {
+            Ok(UpgradeableLoaderState::ProgramData { slot, .. }) => {
+                program_datas.push((address, slot))
+            }
+            _ => return Err(anyhow!("Error parsing ProgramData account {}", address)),
+        }
+    }
+
+    let program_ids = if program_datas.is_empty() {
+        HashMap::new()
+    } else {
+        get_program_ids(&config.client)?
+    };
+
+    let mut programs = vec![];
+    for chunk in program_datas.chunks(FULL_FETCH_BATCH_SIZE) {
+        let addresses: Vec<Pubkey> = chunk.iter().map(|(address, _)| *address).collect();
+        let accounts = config
+            .client
+            .get_multiple_accounts_with_config(
+                &addresses,
+                RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64Zstd),
+                    ..RpcAccountInfoConfig::default()
+                },
+            )?
+            .value;
+
+        for ((program_data, slot), account) in chunk.iter().zip(accounts) {
+            let account = match account {
+                Some(account) => account,
+                None => continue,
+            };
+
+            let program_id = match program_ids.get(program_data) {
+                Some(program_id) => *program_id,
+                None => {
+                    eprintln!("No program account points at ProgramData {program_data}");
+                    continue;
+                }
+            };
+
+            programs.push(ProgramSummary {
+                program_id,
+                program_data: *program_data,
+                slot: *slot,
+                data_len: account.data.len(),
+                hash: program_data_hash(&account.data),
+            });
+        }
+    }
+
+    programs.sort_by_key(|program| program.program_id.to_string());
+
+    Ok(ProgramList(programs))
+}
+
+/// Maps every ProgramData account to the program pointing at it. The ProgramData address is
+/// derived from the program id, so it can't be reversed, and a single scan of the program
+/// accounts is far cheaper than one per program.
+fn get_program_ids(client: &RpcClient) -> Result<HashMap<Pubkey, Pubkey>> {
+    let filters = vec![
+        RpcFilterType::DataSize(UpgradeableLoaderState::size_of_program() as u64),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &[2, 0, 0, 0])),
+    ];
+
+    let results = client.get_program_accounts_with_config(
+        &bpf_loader_upgradeable::id(),
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig {
+                    offset: ACCOUNT_TYPE_SIZE,
+                    length: PUBKEY_LEN,
+                }),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    results
+        .into_iter()
+        .map(|(program_id, account)| {
+            let program_data = Pubkey::try_from(account.data.as_slice())
+                .map_err(|_| anyhow!("Error parsing Program account {program_id}"))?;
+            Ok((program_data, program_id))
+        })
+        .collect()
+}
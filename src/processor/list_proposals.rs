@@ -0,0 +1,114 @@
+use super::*;
+
+pub struct ListProposalsArgs {
+    pub global: GlobalArgs,
+    pub states: Vec<ProposalState>,
+    pub mint_type: Option<MintType>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionTally {
+    pub label: String,
+    pub vote_weight: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalSummary {
+    pub index: u32,
+    #[serde(with = "display_fromstr")]
+    pub address: Pubkey,
+    pub state: String,
+    pub mint_type: String,
+    pub name: String,
+    pub options: Vec<OptionTally>,
+    pub deny_vote_weight: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct ProposalList(pub Vec<ProposalSummary>);
+
+impl fmt::Display for ProposalList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, proposal) in self.0.iter().enumerate() {
+            let mut tally: Vec<String> = proposal
+                .options
+                .iter()
+                .map(|option| format!("{}: {}", option.label, option.vote_weight))
+                .collect();
+            if let Some(deny_vote_weight) = proposal.deny_vote_weight {
+                tally.push(format!("No: {deny_vote_weight}"));
+            }
+
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{:>4}  {}  {:<20} {:<8} {}  [{}]",
+                proposal.index,
+                proposal.address,
+                proposal.state,
+                proposal.mint_type,
+                proposal.name,
+                tally.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_proposal_state(s: &str) -> Result<ProposalState> {
+    match s.to_lowercase().replace(['-', '_'], "").as_str() {
+        "draft" => Ok(ProposalState::Draft),
+        "signingoff" => Ok(ProposalState::SigningOff),
+        "voting" => Ok(ProposalState::Voting),
+        "succeeded" => Ok(ProposalState::Succeeded),
+        "executing" => Ok(ProposalState::Executing),
+        "executingwitherrors" => Ok(ProposalState::ExecutingWithErrors),
+        "completed" => Ok(ProposalState::Completed),
+        "cancelled" | "canceled" => Ok(ProposalState::Cancelled),
+        "defeated" => Ok(ProposalState::Defeated),
+        _ => Err(anyhow!("Invalid proposal state")),
+    }
+}
+
+pub fn list_proposals(args: ListProposalsArgs) -> Result<ProposalList> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let mint_types = match args.mint_type {
+        Some(mint_type) => vec![mint_type],
+        None => vec![MintType::Member, MintType::Council],
+    };
+
+    let mut proposals = get_governance_proposals(&config, &mint_types)?;
+
+    proposals.sort_by_key(|(index, _, _, _)| *index);
+
+    let proposals = proposals
+        .into_iter()
+        .filter(|(_, _, _, proposal)| {
+            args.states.is_empty() || args.states.contains(&proposal.state)
+        })
+        .map(|(index, mint_type, address, proposal)| ProposalSummary {
+            index,
+            address,
+            state: format!("{:?}", proposal.state),
+            mint_type: format!("{:?}", mint_type),
+            name: proposal.name,
+            options: proposal
+                .options
+                .into_iter()
+                .map(|option| OptionTally {
+                    label: option.label,
+                    vote_weight: option.vote_weight,
+                })
+                .collect(),
+            deny_vote_weight: proposal.deny_vote_weight,
+        })
+        .collect();
+
+    Ok(ProposalList(proposals))
+}
@@ -1,4 +1,9 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    fmt,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
 
 use anyhow::{anyhow, Result};
 use borsh::BorshDeserialize;
@@ -6,15 +11,24 @@ use log::debug;
 use serde::{Deserialize, Serialize};
 use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
 use solana_client::rpc_client::RpcClient;
-use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionConfig,
+};
 use solana_client::rpc_filter::{Memcmp, RpcFilterType};
 use solana_program::{
     bpf_loader_upgradeable::{self, UpgradeableLoaderState},
-    instruction::AccountMeta,
+    clock::{Clock, UnixTimestamp},
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
+    sysvar,
 };
+use solana_sdk::account::from_account;
 use solana_sdk::account_utils::StateMut;
-use solana_sdk::signer::Signer;
+use solana_sdk::{
+    message::Message, packet::PACKET_DATA_SIZE, signature::Signature, signer::Signer,
+    system_instruction::MAX_PERMITTED_DATA_LENGTH, transaction::Transaction,
+};
 use spl_associated_token_account::get_associated_token_address;
 use spl_governance::{
     instruction::{
@@ -22,9 +36,14 @@ use spl_governance::{
         execute_transaction, insert_transaction, sign_off_proposal, withdraw_governing_tokens,
     },
     state::{
+        enums::{GovernanceAccountType, ProposalState, TransactionExecutionStatus},
         governance::GovernanceV2,
-        proposal::{get_proposal_address, ProposalV2},
-        proposal_transaction::{get_proposal_transaction_address, ProposalTransactionV2},
+        native_treasury::get_native_treasury_address,
+        proposal::{get_proposal_address, OptionVoteResult, ProposalV2},
+        proposal_transaction::{
+            get_proposal_transaction_address, AccountMetaData, InstructionData,
+            ProposalTransactionV2,
+        },
         token_owner_record::{get_token_owner_record_address, TokenOwnerRecordV2},
         vote_record::Vote as SplVote,
     },
@@ -32,29 +51,57 @@ use spl_governance::{
 };
 use std::mem::size_of;
 
-use crate::{config, instruction::create_upgrade_program_instruction, Vote, GOVERNANCE_PROGRAM_ID};
+use crate::{
+    args::GlobalArgs,
+    config,
+    instruction::{create_upgrade_program_instruction, format_instruction},
+    output::{display_fromstr, format_signature, option_signature, SignatureOutput},
+    Vote, GOVERNANCE_PROGRAM_ID,
+};
 
 mod cancel;
 mod close_buffers;
+mod close_program;
 mod deposit;
 mod execute;
+mod extend_program;
 mod get_buffers;
 mod get_gov_config;
+mod hash;
+mod list_programs;
+mod list_proposals;
+mod proposal_builder;
+mod propose;
+mod set_authority;
+mod show_proposal;
+mod transfer_authority;
 mod update_config;
 mod upgrade_program;
 mod vote;
 mod withdraw;
+mod write_buffer;
 
 pub use cancel::*;
 pub use close_buffers::*;
+pub use close_program::*;
 pub use deposit::*;
 pub use execute::*;
+pub use extend_program::*;
 pub use get_buffers::*;
 pub use get_gov_config::*;
+pub use hash::*;
+pub use list_programs::*;
+pub use list_proposals::*;
+pub use proposal_builder::*;
+pub use propose::*;
+pub use set_authority::*;
+pub use show_proposal::*;
+pub use transfer_authority::*;
 pub use update_config::*;
 pub use upgrade_program::*;
 pub use vote::*;
 pub use withdraw::*;
+pub use write_buffer::*;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum MintType {
@@ -74,6 +121,58 @@ impl FromStr for MintType {
     }
 }
 
+/// Whether voters pick a single option or may approve several options independently.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChoiceType {
+    Single,
+    Multi,
+}
+
+impl FromStr for ChoiceType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "single" | "single-choice" => Ok(ChoiceType::Single),
+            "multi" | "multi-choice" => Ok(ChoiceType::Multi),
+            _ => Err(anyhow!("Invalid choice type")),
+        }
+    }
+}
+
+impl ChoiceType {
+    /// Multi choice is the default when several options are given so each can pass on its own.
+    pub fn vote_type(choice_type: Option<&ChoiceType>, options_count: usize) -> VoteType {
+        let choice_type = choice_type.cloned().unwrap_or(if options_count > 1 {
+            ChoiceType::Multi
+        } else {
+            ChoiceType::Single
+        });
+
+        match choice_type {
+            // The program requires a multi choice proposal to have more than one option, with
+            // both limits equal to the number of options.
+            ChoiceType::Multi if options_count > 1 => VoteType::MultiChoice {
+                max_voter_options: options_count as u8,
+                max_winning_options: options_count as u8,
+            },
+            _ => VoteType::SingleChoice,
+        }
+    }
+}
+
+impl MintType {
+    pub fn governing_token_mint(&self, realm: &RealmV2) -> Result<Pubkey> {
+        match self {
+            MintType::Member => Ok(realm.community_mint),
+            MintType::Council => realm
+                .config
+                .council_mint
+                .ok_or_else(|| anyhow!("Council mint not found")),
+        }
+    }
+}
+
 fn get_realm_data(client: &RpcClient, realm: &Pubkey) -> Result<RealmV2> {
     let account = client.get_account(realm)?;
     let realm_data = RealmV2::deserialize(&mut account.data.as_slice())?;
@@ -86,6 +185,30 @@ fn get_governance_data(client: &RpcClient, governance: &Pubkey) -> Result<Govern
     Ok(governance_data)
 }
 
+/// Like `get_governance_state`, but `None` when the account doesn't exist, such as a removed
+/// proposal transaction. Any other error, including from the RPC, is returned.
+fn get_governance_state_if_exists<T>(client: &RpcClient, address: &Pubkey) -> Result<Option<T>>
+where
+    T: borsh::BorshDeserialize,
+{
+    match client
+        .get_account_with_commitment(address, client.commitment())?
+        .value
+    {
+        Some(account) => Ok(Some(T::deserialize(&mut account.data.as_slice())?)),
+        None => Ok(None),
+    }
+}
+
+/// The cluster's current time from the Clock sysvar. Unlike the block time of a recent slot,
+/// it's always available.
+fn get_unix_timestamp(client: &RpcClient) -> Result<UnixTimestamp> {
+    let account = client.get_account(&sysvar::clock::id())?;
+    let clock: Clock =
+        from_account(&account).ok_or_else(|| anyhow!("Error parsing the Clock sysvar"))?;
+    Ok(clock.unix_timestamp)
+}
+
 fn get_governance_state<T>(client: &RpcClient, governance: &Pubkey) -> Result<T>
 where
     T: borsh::BorshDeserialize,
@@ -94,3 +217,462 @@ where
     let governance_data = T::deserialize(&mut account.data.as_slice())?;
     Ok(governance_data)
 }
+
+/// An upgradeable program along with the state of its ProgramData account.
+#[derive(Debug, Clone)]
+pub struct UpgradeableProgram {
+    pub program_id: Pubkey,
+    pub program_data: Pubkey,
+    pub upgrade_authority: Option<Pubkey>,
+    pub slot: u64,
+    /// Size of the ProgramData account, including its metadata.
+    pub data_len: usize,
+    pub lamports: u64,
+    /// Hash of the deployed executable, see `program_data_hash`.
+    pub hash: String,
+}
+
+/// Reads the ProgramData address from the program account rather than deriving it, so a program
+/// id that isn't an upgradeable program fails here instead of at execution.
+fn get_upgradeable_program(client: &RpcClient, program_id: &Pubkey) -> Result<UpgradeableProgram> {
+    let program_account = client
+        .get_account(program_id)
+        .map_err(|_| anyhow!("Program {program_id} not found"))?;
+
+    if program_account.owner != bpf_loader_upgradeable::id() {
+        return Err(anyhow!("{program_id} is not an upgradeable program"));
+    }
+
+    let program_data = match program_account.state() {
+        Ok(UpgradeableLoaderState::Program {
+            programdata_address,
+        }) => programdata_address,
+        _ => return Err(anyhow!("{program_id} is not an upgradeable program")),
+    };
+
+    let program_data_account = client
+        .get_account(&program_data)
+        .map_err(|_| anyhow!("ProgramData {program_data} of {program_id} not found"))?;
+
+    match program_data_account.state() {
+        Ok(UpgradeableLoaderState::ProgramData {
+            slot,
+            upgrade_authority_address,
+        }) => Ok(UpgradeableProgram {
+            program_id: *program_id,
+            program_data,
+            upgrade_authority: upgrade_authority_address,
+            slot,
+            data_len: program_data_account.data.len(),
+            lamports: program_data_account.lamports,
+            hash: program_data_hash(&program_data_account.data),
+        }),
+        _ => Err(anyhow!(
+            "Error parsing ProgramData account {program_data} of {program_id}"
+        )),
+    }
+}
+
+/// The program given on the command line, or else by `PROGRAM_ID` or the profile.
+fn resolve_program_id(config: &config::CliConfig, program_id: Option<Pubkey>) -> Result<Pubkey> {
+    program_id.or(config.program_id).ok_or_else(|| {
+        anyhow!("No program id provided, use --program-id, PROGRAM_ID or a profile program_id")
+    })
+}
+
+/// Resolves and fetches a program, failing unless `authority` holds its upgrade authority.
+fn get_program_with_authority(
+    config: &config::CliConfig,
+    program_id: Option<Pubkey>,
+    authority: &Pubkey,
+) -> Result<UpgradeableProgram> {
+    let program_id = resolve_program_id(config, program_id)?;
+    let program = get_upgradeable_program(&config.client, &program_id)?;
+
+    match program.upgrade_authority {
+        Some(upgrade_authority) if upgrade_authority == *authority => Ok(program),
+        Some(upgrade_authority) => Err(anyhow!(
+            "Program {program_id} upgrade authority is {upgrade_authority}, not {authority}"
+        )),
+        None => Err(anyhow!("Program {program_id} is immutable")),
+    }
+}
+
+/// A program the governance can upgrade. The governance signs every proposed loader
+/// instruction, so anything else would only fail once the proposal executes.
+fn get_governed_program(
+    config: &config::CliConfig,
+    program_id: Option<Pubkey>,
+) -> Result<UpgradeableProgram> {
+    get_program_with_authority(config, program_id, &config.governance_id)
+}
+
+/// Fetches a buffer account, failing if it isn't a buffer or can no longer be written to.
+fn get_upgradeable_buffer(client: &RpcClient, address: &Pubkey) -> Result<UpgradeableBuffer> {
+    let account = client
+        .get_account(address)
+        .map_err(|_| anyhow!("Buffer {address} not found"))?;
+
+    if account.owner != bpf_loader_upgradeable::id() {
+        return Err(anyhow!("{address} is not a buffer account"));
+    }
+
+    match account.state() {
+        Ok(UpgradeableLoaderState::Buffer {
+            authority_address: Some(authority),
+        }) => Ok(UpgradeableBuffer {
+            address: *address,
+            authority,
+            data_len: account.data.len(),
+            lamports: account.lamports,
+            hash: Some(buffer_data_hash(&account.data)),
+        }),
+        Ok(UpgradeableLoaderState::Buffer {
+            authority_address: None,
+        }) => Err(anyhow!("Buffer {address} has no authority")),
+        _ => Err(anyhow!("{address} is not a buffer account")),
+    }
+}
+
+/// ExtendProgram instructions growing a governed program's ProgramData by `additional_bytes`,
+/// paid for by the governance's native treasury, which signs when the proposal executes.
+///
+/// The loader runs as a CPI from the governance, which can only grow an account by
+/// `MAX_PERMITTED_DATA_INCREASE` per instruction, so each instruction needs its own proposal
+/// transaction.
+fn create_extend_program_instructions(
+    config: &config::CliConfig,
+    program: &UpgradeableProgram,
+    additional_bytes: usize,
+) -> Result<Vec<InstructionData>> {
+    let new_len = program
+        .data_len
+        .checked_add(additional_bytes)
+        .filter(|new_len| *new_len <= MAX_PERMITTED_DATA_LENGTH as usize)
+        .ok_or_else(|| {
+            anyhow!(
+                "ProgramData {} can't grow by {additional_bytes} bytes, the maximum size is {MAX_PERMITTED_DATA_LENGTH}",
+                program.program_data
+            )
+        })?;
+
+    let native_treasury =
+        get_native_treasury_address(&GOVERNANCE_PROGRAM_ID, &config.governance_id);
+    let rent = config
+        .client
+        .get_minimum_balance_for_rent_exemption(new_len)?
+        .saturating_sub(program.lamports);
+    let treasury_balance = config.client.get_balance(&native_treasury)?;
+    if treasury_balance < rent {
+        eprintln!(
+            "Warning: native treasury {native_treasury} holds {treasury_balance} lamports but extending ProgramData needs {rent}, fund it before executing the proposal"
+        );
+    }
+
+    let mut instructions = vec![];
+    let mut remaining = additional_bytes;
+    while remaining > 0 {
+        let bytes = remaining.min(MAX_PERMITTED_DATA_INCREASE);
+        instructions.push(into_instruction_data(
+            bpf_loader_upgradeable::extend_program(
+                &program.program_id,
+                Some(&native_treasury),
+                u32::try_from(bytes)
+                    .map_err(|_| anyhow!("Can't extend by {bytes} bytes at once"))?,
+            ),
+        ));
+        remaining -= bytes;
+    }
+
+    Ok(instructions)
+}
+
+fn into_instruction_data(instruction: Instruction) -> InstructionData {
+    let accounts = instruction
+        .accounts
+        .into_iter()
+        .map(into_account_meta_data)
+        .collect();
+
+    InstructionData {
+        program_id: instruction.program_id,
+        accounts,
+        data: instruction.data,
+    }
+}
+
+fn into_account_meta_data(account: AccountMeta) -> AccountMetaData {
+    AccountMetaData {
+        pubkey: account.pubkey,
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }
+}
+
+/// Serialized size of a transaction carrying the instructions, including its signatures.
+fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    let signatures = message.header.num_required_signatures as usize;
+
+    // Short vec length prefix followed by the signatures and the message.
+    1 + signatures * 64 + message.serialize().len()
+}
+
+/// Packs instructions in order into as few transactions as fit within the packet size limit.
+fn pack_instructions(instructions: Vec<Instruction>, payer: &Pubkey) -> Result<Vec<Transaction>> {
+    let mut transactions = vec![];
+    let mut current: Vec<Instruction> = vec![];
+
+    for instruction in instructions {
+        current.push(instruction);
+
+        let size = transaction_size(&current, payer);
+        if size > PACKET_DATA_SIZE {
+            if current.len() == 1 {
+                return Err(anyhow!(
+                    "Instruction is {size} bytes, more than the {PACKET_DATA_SIZE} bytes that fit in a transaction"
+                ));
+            }
+            let instruction = current.pop().expect("current has several instructions");
+            transactions.push(Transaction::new_with_payer(&current, Some(payer)));
+            current = vec![instruction];
+        }
+    }
+
+    if !current.is_empty() {
+        transactions.push(Transaction::new_with_payer(&current, Some(payer)));
+    }
+
+    Ok(transactions)
+}
+
+/// Maximum number of accounts `getMultipleAccounts` accepts per request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+/// Number of buffer or ProgramData accounts fetched in full per request, as each can hold a
+/// whole program.
+const FULL_FETCH_BATCH_SIZE: usize = 10;
+
+// Layout of the upgradeable loader account states, for filtering and slicing their data.
+const ACCOUNT_TYPE_SIZE: usize = 4;
+const SLOT_SIZE: usize = size_of::<u64>();
+const OPTION_SIZE: usize = 1;
+const PUBKEY_LEN: usize = 32;
+
+/// Fetches the governance's proposals created with any of the mints, with their index.
+fn get_governance_proposals(
+    config: &config::CliConfig,
+    mint_types: &[MintType],
+) -> Result<Vec<(u32, MintType, Pubkey, ProposalV2)>> {
+    let realm = get_realm_data(&config.client, &config.realm_id)?;
+    let governance = get_governance_data(&config.client, &config.governance_id)?;
+
+    // Each proposal index is used once per governance, by whichever mint created it, so derive
+    // every index for every mint and keep the accounts that exist.
+    let mut candidates = vec![];
+    for mint_type in mint_types {
+        let governing_token_mint = match mint_type.governing_token_mint(&realm) {
+            Ok(mint) => mint,
+            Err(_) => continue,
+        };
+
+        for index in 0..governance.proposals_count {
+            let address = get_proposal_address(
+                &GOVERNANCE_PROGRAM_ID,
+                &config.governance_id,
+                &governing_token_mint,
+                &index.to_le_bytes(),
+            );
+            candidates.push((index, mint_type.clone(), address));
+        }
+    }
+
+    let mut proposals = vec![];
+    for chunk in candidates.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let addresses: Vec<Pubkey> = chunk.iter().map(|(_, _, address)| *address).collect();
+        let accounts = config.client.get_multiple_accounts(&addresses)?;
+
+        for ((index, mint_type, address), account) in chunk.iter().zip(accounts) {
+            if let Some(account) = account {
+                let proposal = ProposalV2::deserialize(&mut account.data.as_slice())?;
+                proposals.push((*index, mint_type.clone(), *address, proposal));
+            }
+        }
+    }
+
+    Ok(proposals)
+}
+
+/// Fetches the transactions inserted into a proposal, including executed ones.
+fn get_proposal_transactions(
+    client: &RpcClient,
+    proposal: &Pubkey,
+) -> Result<Vec<(Pubkey, ProposalTransactionV2)>> {
+    let filters = vec![
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            0,
+            &[GovernanceAccountType::ProposalTransactionV2 as u8],
+        )),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(1, proposal.as_ref())),
+    ];
+
+    let results = client.get_program_accounts_with_config(
+        &GOVERNANCE_PROGRAM_ID,
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    results
+        .into_iter()
+        .map(|(address, account)| {
+            Ok((
+                address,
+                ProposalTransactionV2::deserialize(&mut account.data.as_slice())?,
+            ))
+        })
+        .collect()
+}
+
+/// Fetches the transactions of a proposal option by index, with their index and address.
+fn get_option_transactions(
+    client: &RpcClient,
+    proposal: &Pubkey,
+    option_index: u8,
+    transactions_next_index: u16,
+) -> Result<Vec<(u16, Pubkey, ProposalTransactionV2)>> {
+    let mut transactions = vec![];
+
+    for transaction_index in 0..transactions_next_index {
+        let address = get_proposal_transaction_address(
+            &GOVERNANCE_PROGRAM_ID,
+            proposal,
+            &option_index.to_le_bytes(),
+            &transaction_index.to_le_bytes(),
+        );
+
+        // Removed transactions leave gaps in the indexes.
+        if let Some(transaction) = get_governance_state_if_exists(client, &address)? {
+            transactions.push((transaction_index, address, transaction));
+        }
+    }
+
+    Ok(transactions)
+}
+
+/// Resolves `--latest` to the most recent proposal of the governance for the mint.
+fn resolve_proposal_id(
+    config: &config::CliConfig,
+    mint_type: &MintType,
+    proposal_id: Option<Pubkey>,
+    latest: bool,
+) -> Result<Pubkey> {
+    if latest {
+        let realm = get_realm_data(&config.client, &config.realm_id)?;
+        let governing_token_mint = mint_type.governing_token_mint(&realm)?;
+        let governance = get_governance_data(&config.client, &config.governance_id)?;
+        let proposal_index = governance
+            .proposals_count
+            .checked_sub(1)
+            .ok_or_else(|| anyhow!("Governance {} has no proposals", config.governance_id))?;
+
+        Ok(get_proposal_address(
+            &GOVERNANCE_PROGRAM_ID,
+            &config.governance_id,
+            &governing_token_mint,
+            &proposal_index.to_le_bytes(),
+        ))
+    } else if let Some(proposal_id) = proposal_id {
+        Ok(proposal_id)
+    } else {
+        Err(anyhow!("Either --latest or --proposal-id must be provided"))
+    }
+}
+
+/// Prints a warning about an action that can't be undone and waits for the user to type "yes",
+/// unless `--yes` was passed or nothing will be sent.
+fn confirm(config: &config::CliConfig, warning: &str, yes: bool) -> Result<()> {
+    eprintln!("WARNING: {warning}");
+    if yes || config.dry_run {
+        return Ok(());
+    }
+
+    eprint!("Type \"yes\" to continue: ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if answer.trim() != "yes" {
+        return Err(anyhow!("Aborted"));
+    }
+
+    Ok(())
+}
+
+/// Sends the transaction, or in dry run mode simulates it and prints what it would have done.
+fn send_transaction(config: &config::CliConfig, tx: &Transaction) -> Result<Option<Signature>> {
+    if config.dry_run {
+        simulate_transaction(config, tx)?;
+        return Ok(None);
+    }
+
+    Ok(Some(
+        config
+            .client
+            .send_and_confirm_transaction_with_spinner(tx)?,
+    ))
+}
+
+fn simulate_transaction(config: &config::CliConfig, tx: &Transaction) -> Result<()> {
+    let result = config
+        .client
+        .simulate_transaction_with_config(
+            tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(config.client.commitment()),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+    let fee = config.client.get_fee_for_message(&tx.message)?;
+
+    eprintln!("Dry run, transaction not sent");
+    eprintln!("Instructions:");
+    for (i, compiled) in tx.message.instructions.iter().enumerate() {
+        let message = &tx.message;
+        let instruction = InstructionData {
+            program_id: message.account_keys[compiled.program_id_index as usize],
+            accounts: compiled
+                .accounts
+                .iter()
+                .map(|index| AccountMetaData {
+                    pubkey: message.account_keys[*index as usize],
+                    is_signer: message.is_signer(*index as usize),
+                    is_writable: message.is_writable(*index as usize),
+                })
+                .collect(),
+            data: compiled.data.clone(),
+        };
+        eprintln!("  #{i}");
+        eprintln!("{}", format_instruction(&instruction, 4));
+    }
+    eprintln!("Fee: {fee} lamports");
+    if let Some(units_consumed) = result.units_consumed {
+        eprintln!("Compute units: {units_consumed}");
+    }
+    eprintln!("Logs:");
+    for log in result.logs.unwrap_or_default() {
+        eprintln!("  {log}");
+    }
+    if let Some(err) = result.err {
+        return Err(anyhow!("Simulation failed: {err}"));
+    }
+    eprintln!("Simulation succeeded");
+
+    Ok(())
+}
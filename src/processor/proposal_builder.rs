@@ -0,0 +1,627 @@
+use spl_governance::state::signatory_record::get_signatory_record_address;
+
+use super::*;
+
+/// A set of instructions to be executed together once the proposal option it belongs to passes.
+#[derive(Debug, Clone)]
+pub struct ProposalTransaction {
+    pub option_index: u8,
+    pub hold_up_time: Option<u32>,
+    pub instructions: Vec<InstructionData>,
+}
+
+impl ProposalTransaction {
+    pub fn new(option_index: u8, instructions: Vec<InstructionData>) -> Self {
+        Self {
+            option_index,
+            hold_up_time: None,
+            instructions,
+        }
+    }
+    /// Overrides the governance's `min_transaction_hold_up_time` for this transaction.
+    pub fn hold_up_time(mut self, hold_up_time: u32) -> Self {
+        self.hold_up_time = Some(hold_up_time);
+        self
+    }
+    /// Splits independent instructions into as few proposal transactions as possible while
+    /// keeping each `insert_transaction` small enough to fit in a single Solana transaction.
+    pub fn split(option_index: u8, instructions: Vec<InstructionData>) -> Result<Vec<Self>> {
+        // Stand-in accounts for measuring, every insert_transaction has the same shape.
+        let governance = Pubkey::new_unique();
+        let proposal = Pubkey::new_unique();
+        let token_owner_record = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+
+        let insert_size = |instructions: &[InstructionData]| {
+            transaction_size(
+                &[insert_transaction(
+                    &GOVERNANCE_PROGRAM_ID,
+                    &governance,
+                    &proposal,
+                    &token_owner_record,
+                    &payer,
+                    &payer,
+                    option_index,
+                    0,
+                    0,
+                    instructions.to_vec(),
+                )],
+                &payer,
+            )
+        };
+
+        let mut transactions = vec![];
+        let mut current: Vec<InstructionData> = vec![];
+
+        for instruction in instructions {
+            current.push(instruction);
+
+            if insert_size(&current) > PACKET_DATA_SIZE {
+                let instruction = current.pop().expect("current is not empty");
+                if current.is_empty() {
+                    return Err(anyhow!(
+                        "Instruction for program {} is too large to insert into a proposal",
+                        instruction.program_id
+                    ));
+                }
+                transactions.push(Self::new(option_index, current));
+                current = vec![instruction];
+            }
+        }
+
+        if !current.is_empty() {
+            transactions.push(Self::new(option_index, current));
+        }
+
+        Ok(transactions)
+    }
+}
+
+/// Longest description a new proposal with this name and options can have while its
+/// `create_proposal` instruction still fits in a single Solana transaction.
+pub fn max_description_len(
+    name: &str,
+    options: &[String],
+    choice_type: Option<&ChoiceType>,
+) -> usize {
+    let options = if options.is_empty() {
+        vec!["Approve".to_string()]
+    } else {
+        options.to_vec()
+    };
+    let vote_type = ChoiceType::vote_type(choice_type, options.len());
+
+    // Stand-in accounts for measuring, as in `ProposalTransaction::split`. They have to be
+    // distinct, as repeated accounts are only serialized once.
+    let governance = Pubkey::new_unique();
+    let token_owner_record = Pubkey::new_unique();
+    let realm = Pubkey::new_unique();
+    let governing_token_mint = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+
+    let size = transaction_size(
+        &[create_proposal(
+            &GOVERNANCE_PROGRAM_ID,
+            &governance,
+            &token_owner_record,
+            &payer,
+            &payer,
+            None,
+            &realm,
+            name.to_string(),
+            String::new(),
+            &governing_token_mint,
+            vote_type,
+            options,
+            true,
+            0,
+        )],
+        &payer,
+    );
+
+    // The instruction data length prefix can grow by a byte once the description is filled in.
+    PACKET_DATA_SIZE.saturating_sub(size + 1)
+}
+
+/// Builds the create, add-signatory, insert-transaction and sign-off instructions for a
+/// proposal on the configured governance, or the insert instructions for an existing draft.
+#[derive(Debug, Default)]
+pub struct ProposalBuilder {
+    pub proposal: Option<Pubkey>,
+    pub resume: Option<Pubkey>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub mint_type: Option<MintType>,
+    pub choice_type: Option<ChoiceType>,
+    pub options: Vec<String>,
+    pub transactions: Vec<ProposalTransaction>,
+    pub sign_off: Option<bool>,
+}
+
+/// The transactions creating a proposal, in the order they have to be sent, along with where
+/// the proposal will live.
+pub struct PreparedProposal {
+    pub address: Pubkey,
+    pub index: u32,
+    pub governing_token_mint: Pubkey,
+    pub transactions: Vec<Transaction>,
+}
+
+/// Where a proposal sent by a command lives, so scripts don't have to guess it with `--latest`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalCreated {
+    #[serde(with = "display_fromstr")]
+    pub address: Pubkey,
+    pub index: u32,
+    #[serde(with = "display_fromstr")]
+    pub governing_token_mint: Pubkey,
+    #[serde(with = "option_signature")]
+    pub signature: Option<Signature>,
+}
+
+impl fmt::Display for ProposalCreated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Proposal: {}", self.address)?;
+        writeln!(f, "Index: {}", self.index)?;
+        writeln!(f, "Governing Token Mint: {}", self.governing_token_mint)?;
+        write!(f, "Signature: {}", format_signature(&self.signature))
+    }
+}
+
+impl ProposalBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds the transactions to an existing draft proposal instead of creating a new one.
+    pub fn proposal(mut self, proposal: Pubkey) -> Self {
+        self.proposal = Some(proposal);
+        self
+    }
+    /// Continues creating a proposal that was interrupted part way through, skipping the
+    /// transactions that were already inserted.
+    pub fn resume(mut self, proposal: Option<Pubkey>) -> Self {
+        self.resume = proposal;
+        self
+    }
+    pub fn name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+    pub fn description(mut self, description: String) -> Self {
+        self.description = Some(description);
+        self
+    }
+    pub fn mint_type(mut self, mint_type: MintType) -> Self {
+        self.mint_type = Some(mint_type);
+        self
+    }
+    pub fn choice_type(mut self, choice_type: Option<ChoiceType>) -> Self {
+        self.choice_type = choice_type;
+        self
+    }
+    pub fn options(mut self, options: Vec<String>) -> Self {
+        self.options = options;
+        self
+    }
+    pub fn transaction(mut self, transaction: ProposalTransaction) -> Self {
+        self.transactions.push(transaction);
+        self
+    }
+    pub fn transactions(mut self, transactions: Vec<ProposalTransaction>) -> Self {
+        self.transactions.extend(transactions);
+        self
+    }
+    /// Whether to sign off the proposal once the transactions are inserted, defaults to true.
+    /// Leaving it unsigned keeps it in Draft so more transactions can be added later.
+    pub fn sign_off(mut self, sign_off: bool) -> Self {
+        self.sign_off = Some(sign_off);
+        self
+    }
+    pub fn build(self, config: &config::CliConfig) -> Result<PreparedProposal> {
+        let governance = get_governance_data(&config.client, &config.governance_id)?;
+
+        let mut instructions = vec![];
+
+        let existing = match (self.proposal, self.resume) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow!(
+                    "Cannot both add to an existing proposal and resume one"
+                ))
+            }
+            (Some(address), None) | (None, Some(address)) => {
+                let proposal: ProposalV2 = get_governance_state(&config.client, &address)
+                    .map_err(|_| anyhow!("Proposal {address} not found"))?;
+
+                if proposal.governance != config.governance_id {
+                    return Err(anyhow!(
+                        "Proposal {address} does not belong to governance {}",
+                        config.governance_id
+                    ));
+                }
+                if proposal.state != ProposalState::Draft {
+                    return Err(anyhow!(
+                        "Proposal {address} is {:?}, only Draft proposals can be extended",
+                        proposal.state
+                    ));
+                }
+
+                Some((address, proposal))
+            }
+            (None, None) => None,
+        };
+
+        let (proposal_address, proposal_index, governing_token_mint, token_owner_record) =
+            match &existing {
+                Some((proposal_address, proposal)) => {
+                    let proposal_index = find_proposal_index(
+                        &config.governance_id,
+                        &proposal.governing_token_mint,
+                        governance.proposals_count,
+                        proposal_address,
+                    )?;
+
+                    (
+                        *proposal_address,
+                        proposal_index,
+                        proposal.governing_token_mint,
+                        proposal.token_owner_record,
+                    )
+                }
+                None => {
+                    let realm = get_realm_data(&config.client, &config.realm_id)?;
+                    let governing_token_mint = self
+                        .mint_type
+                        .unwrap_or(MintType::Council)
+                        .governing_token_mint(&realm)?;
+
+                    let proposal_index: u32 = governance.proposals_count;
+
+                    let token_owner_record = get_token_owner_record_address(
+                        &GOVERNANCE_PROGRAM_ID,
+                        &config.realm_id,
+                        &governing_token_mint,
+                        &config.keypair.pubkey(),
+                    );
+
+                    let proposal_address = get_proposal_address(
+                        &GOVERNANCE_PROGRAM_ID,
+                        &config.governance_id,
+                        &governing_token_mint,
+                        &proposal_index.to_le_bytes(),
+                    );
+
+                    (
+                        proposal_address,
+                        proposal_index,
+                        governing_token_mint,
+                        token_owner_record,
+                    )
+                }
+            };
+
+        debug!("Governing Token Mint: {governing_token_mint}");
+        debug!("Proposal index: {proposal_index}");
+        debug!("Token Owner Record: {token_owner_record}");
+        debug!("Proposal Address: {proposal_address}");
+
+        // Transaction indexes are sequential within each option. Appending to a draft continues
+        // from each option's on-chain next index, while a new or resumed proposal starts at zero.
+        let mut next_indexes: Vec<u16> = match (&existing, self.resume) {
+            (Some((_, proposal)), None) => proposal
+                .options
+                .iter()
+                .map(|option| option.transactions_next_index)
+                .collect(),
+            (Some((_, proposal)), Some(_)) => vec![0; proposal.options.len()],
+            (None, _) => {
+                let name = self
+                    .name
+                    .ok_or_else(|| anyhow!("No proposal name provided"))?;
+                let description = self
+                    .description
+                    .ok_or_else(|| anyhow!("No proposal description provided"))?;
+
+                // A proposal needs at least one option for its transactions to be attached to.
+                let options = if self.options.is_empty() {
+                    vec!["Approve".to_string()]
+                } else {
+                    self.options
+                };
+
+                let vote_type = ChoiceType::vote_type(self.choice_type.as_ref(), options.len());
+
+                debug!("Vote type: {:?}", vote_type);
+
+                let next_indexes = vec![0; options.len()];
+
+                instructions.push(create_proposal(
+                    &GOVERNANCE_PROGRAM_ID,
+                    &config.governance_id,
+                    &token_owner_record,
+                    &config.keypair.pubkey(),
+                    &config.keypair.pubkey(),
+                    None,
+                    &config.realm_id,
+                    name,
+                    description,
+                    &governing_token_mint,
+                    vote_type,
+                    options,
+                    true,
+                    proposal_index,
+                ));
+
+                next_indexes
+            }
+        };
+
+        let signatory_record = get_signatory_record_address(
+            &GOVERNANCE_PROGRAM_ID,
+            &proposal_address,
+            &config.keypair.pubkey(),
+        );
+
+        if existing.is_none() || config.client.get_account(&signatory_record).is_err() {
+            instructions.push(add_signatory(
+                &GOVERNANCE_PROGRAM_ID,
+                &proposal_address,
+                &token_owner_record,
+                &config.keypair.pubkey(),
+                &config.keypair.pubkey(),
+                &config.keypair.pubkey(),
+            ));
+        }
+
+        // The program rejects hold up times below the governance minimum.
+        let options_count = next_indexes.len();
+        let min_hold_up_time = governance.config.min_transaction_hold_up_time;
+
+        for transaction in self.transactions {
+            let next_index = next_indexes
+                .get_mut(transaction.option_index as usize)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Transaction targets option {} but the proposal only has {} option(s)",
+                        transaction.option_index,
+                        options_count
+                    )
+                })?;
+            let index = *next_index;
+            *next_index += 1;
+
+            let hold_up_time = transaction.hold_up_time.unwrap_or(min_hold_up_time);
+            if hold_up_time < min_hold_up_time {
+                return Err(anyhow!(
+                    "Hold up time {hold_up_time}s is below the governance minimum of {min_hold_up_time}s"
+                ));
+            }
+
+            if self.resume.is_some() {
+                let proposal_transaction_address = get_proposal_transaction_address(
+                    &GOVERNANCE_PROGRAM_ID,
+                    &proposal_address,
+                    &transaction.option_index.to_le_bytes(),
+                    &index.to_le_bytes(),
+                );
+
+                if let Ok(inserted) = get_governance_state::<ProposalTransactionV2>(
+                    &config.client,
+                    &proposal_transaction_address,
+                ) {
+                    if inserted.instructions != transaction.instructions {
+                        return Err(anyhow!(
+                            "Transaction [{}, {index}] of proposal {proposal_address} does not match, cannot resume",
+                            transaction.option_index
+                        ));
+                    }
+
+                    debug!(
+                        "Transaction [{}, {index}] already inserted",
+                        transaction.option_index
+                    );
+                    continue;
+                }
+            }
+
+            debug!(
+                "Inserting transaction [{}, {index}] with hold up time {hold_up_time}",
+                transaction.option_index
+            );
+
+            instructions.push(insert_transaction(
+                &GOVERNANCE_PROGRAM_ID,
+                &config.governance_id,
+                &proposal_address,
+                &token_owner_record,
+                &config.keypair.pubkey(),
+                &config.keypair.pubkey(),
+                transaction.option_index,
+                index,
+                hold_up_time,
+                transaction.instructions,
+            ));
+        }
+
+        if self.sign_off.unwrap_or(true) {
+            instructions.push(sign_off_proposal(
+                &GOVERNANCE_PROGRAM_ID,
+                &config.realm_id,
+                &config.governance_id,
+                &proposal_address,
+                &config.keypair.pubkey(),
+                None,
+            ));
+        }
+
+        let transactions = pack_instructions(instructions, &config.keypair.pubkey())?;
+
+        debug!("Proposal needs {} transaction(s)", transactions.len());
+
+        Ok(PreparedProposal {
+            address: proposal_address,
+            index: proposal_index,
+            governing_token_mint,
+            transactions,
+        })
+    }
+}
+
+impl PreparedProposal {
+    /// Signs and sends the proposal transactions in order, returning the proposal along with the
+    /// signature of the last one. Each is signed just before sending so a long proposal doesn't
+    /// outlive its blockhash.
+    pub fn send(&self, config: &config::CliConfig) -> Result<ProposalCreated> {
+        if self.transactions.is_empty() {
+            return Err(anyhow!("No proposal transactions to send"));
+        }
+
+        let mut last_signature = None;
+
+        for (i, tx) in self.transactions.iter().enumerate() {
+            // Later transactions act on the proposal the first one creates, so they can only
+            // fail when simulated on their own.
+            if config.dry_run && i > 0 {
+                eprintln!(
+                    "Proposal transaction {} of {} relies on the earlier ones, which were not sent, so it is not simulated",
+                    i + 1,
+                    self.transactions.len()
+                );
+                continue;
+            }
+
+            let mut tx = tx.clone();
+            tx.try_sign(&[&config.keypair], config.client.get_latest_blockhash()?)?;
+
+            match send_transaction(config, &tx) {
+                Ok(signature) => {
+                    debug!(
+                        "Sent proposal transaction {}/{}: {}",
+                        i + 1,
+                        self.transactions.len(),
+                        format_signature(&signature)
+                    );
+                    last_signature = signature;
+                }
+                Err(error) => {
+                    debug!("Error creating proposal: {:?}", error);
+                    if i > 0 {
+                        eprintln!(
+                            "Proposal {} was partially created ({i}/{} transactions sent), re-run with --resume {} to continue",
+                            self.address,
+                            self.transactions.len(),
+                            self.address
+                        );
+                    }
+                    return Err(error);
+                }
+            }
+        }
+
+        Ok(ProposalCreated {
+            address: self.address,
+            index: self.index,
+            governing_token_mint: self.governing_token_mint,
+            signature: last_signature,
+        })
+    }
+}
+
+/// Proposal accounts don't store their own index, so match the address against each index
+/// derived for the governance and mint.
+fn find_proposal_index(
+    governance: &Pubkey,
+    governing_token_mint: &Pubkey,
+    proposals_count: u32,
+    proposal_address: &Pubkey,
+) -> Result<u32> {
+    (0..proposals_count)
+        .rev()
+        .find(|index| {
+            get_proposal_address(
+                &GOVERNANCE_PROGRAM_ID,
+                governance,
+                governing_token_mint,
+                &index.to_le_bytes(),
+            ) == *proposal_address
+        })
+        .ok_or_else(|| anyhow!("Proposal {proposal_address} not found for this governance"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction(data_len: usize) -> InstructionData {
+        InstructionData {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![],
+            data: vec![0; data_len],
+        }
+    }
+
+    fn insert_size(instructions: &[InstructionData]) -> usize {
+        let payer = Pubkey::new_unique();
+        transaction_size(
+            &[insert_transaction(
+                &GOVERNANCE_PROGRAM_ID,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &payer,
+                &payer,
+                0,
+                0,
+                0,
+                instructions.to_vec(),
+            )],
+            &payer,
+        )
+    }
+
+    /// Largest instruction data that still fits in a single insert_transaction.
+    fn max_data_len() -> usize {
+        // Past 127 bytes the data length prefix stops growing, so the size is linear from there.
+        PACKET_DATA_SIZE - insert_size(&[instruction(128)]) + 128
+    }
+
+    #[test]
+    fn split_keeps_small_instructions_together() {
+        let transactions =
+            ProposalTransaction::split(1, vec![instruction(10), instruction(20)]).unwrap();
+
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].option_index, 1);
+        assert_eq!(transactions[0].instructions.len(), 2);
+    }
+
+    #[test]
+    fn split_fills_transactions_up_to_packet_data_size() {
+        let data_len = max_data_len();
+        assert_eq!(insert_size(&[instruction(data_len)]), PACKET_DATA_SIZE);
+
+        let transactions = ProposalTransaction::split(0, vec![instruction(data_len)]).unwrap();
+        assert_eq!(transactions.len(), 1);
+
+        let err = ProposalTransaction::split(0, vec![instruction(data_len + 1)]).unwrap_err();
+        assert!(err.to_string().contains("too large"));
+    }
+
+    #[test]
+    fn split_starts_a_new_transaction_past_packet_data_size() {
+        let half = max_data_len() / 2;
+        let instructions = vec![instruction(half), instruction(half), instruction(10)];
+        let program_ids: Vec<Pubkey> = instructions.iter().map(|ix| ix.program_id).collect();
+
+        let transactions = ProposalTransaction::split(0, instructions).unwrap();
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].instructions.len(), 1);
+        assert_eq!(transactions[1].instructions.len(), 2);
+        let split_ids: Vec<Pubkey> = transactions
+            .iter()
+            .flat_map(|tx| tx.instructions.iter().map(|ix| ix.program_id))
+            .collect();
+        assert_eq!(split_ids, program_ids);
+    }
+}
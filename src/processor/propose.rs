@@ -0,0 +1,83 @@
+use crate::instruction::read_instructions_file;
+
+use super::*;
+
+pub struct ProposeArgs {
+    pub global: GlobalArgs,
+    pub instructions_file: PathBuf,
+    pub proposal_id: Option<Pubkey>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub mint_type: Option<MintType>,
+    pub choice_type: Option<ChoiceType>,
+    pub resume: Option<Pubkey>,
+    pub options: Vec<String>,
+    pub draft: bool,
+}
+
+pub fn propose(args: ProposeArgs) -> Result<ProposalCreated> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let option_specs = read_instructions_file(&args.instructions_file)?;
+
+    debug!("Options: {:#?}", option_specs);
+
+    let mut builder = ProposalBuilder::new()
+        .mint_type(config.mint_type(args.mint_type))
+        .choice_type(args.choice_type)
+        .resume(args.resume)
+        .sign_off(!args.draft);
+
+    if let Some(proposal_id) = args.proposal_id {
+        builder = builder.proposal(proposal_id);
+    } else {
+        // Labels passed on the command line take precedence over the ones in the file.
+        let options = if args.options.is_empty() {
+            option_specs
+                .iter()
+                .enumerate()
+                .map(|(i, option)| {
+                    option.label.clone().unwrap_or_else(|| {
+                        if option_specs.len() == 1 {
+                            "Approve".to_string()
+                        } else {
+                            format!("Option {}", i + 1)
+                        }
+                    })
+                })
+                .collect()
+        } else if args.options.len() == option_specs.len() {
+            args.options
+        } else {
+            return Err(anyhow!(
+                "{} option(s) given but the instructions file describes {}",
+                args.options.len(),
+                option_specs.len()
+            ));
+        };
+
+        builder = builder.options(options);
+
+        if let Some(name) = args.name {
+            builder = builder.name(name);
+        }
+        if let Some(description) = args.description {
+            builder = builder.description(description);
+        }
+    }
+
+    for (option_index, option) in option_specs.into_iter().enumerate() {
+        for transaction in option.transactions {
+            let mut proposal_transaction =
+                ProposalTransaction::new(option_index as u8, transaction.instructions);
+            if let Some(hold_up_time) = transaction.hold_up_time {
+                proposal_transaction = proposal_transaction.hold_up_time(hold_up_time);
+            }
+            builder = builder.transaction(proposal_transaction);
+        }
+    }
+
+    let proposal = builder.build(&config)?;
+
+    proposal.send(&config)
+}
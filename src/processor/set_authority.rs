@@ -0,0 +1,59 @@
+use super::*;
+
+pub struct SetAuthorityArgs {
+    pub global: GlobalArgs,
+    pub program_id: Option<Pubkey>,
+    /// New upgrade authority, or `None` to make the program immutable.
+    pub new_authority: Option<Pubkey>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub mint_type: Option<MintType>,
+    pub yes: bool,
+}
+
+pub fn set_authority(args: SetAuthorityArgs) -> Result<ProposalCreated> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let program = get_governed_program(&config, args.program_id)?;
+    let program_id = program.program_id;
+
+    let (name, description, warning) = match args.new_authority {
+        Some(new_authority) => {
+            if new_authority == config.governance_id {
+                return Err(anyhow!(
+                    "Program {program_id} upgrade authority is already {new_authority}"
+                ));
+            }
+            (
+                format!("Set upgrade authority of {program_id}"),
+                format!("Set the upgrade authority of program {program_id} to {new_authority}"),
+                format!(
+                    "Once executed, only {new_authority} can upgrade {program_id}. The new \
+                     authority doesn't sign, so an address nobody controls makes it immutable."
+                ),
+            )
+        }
+        None => (
+            format!("Make {program_id} immutable"),
+            format!("Remove the upgrade authority of program {program_id}"),
+            format!("Once executed, program {program_id} can never be upgraded or closed again."),
+        ),
+    };
+
+    confirm(&config, &warning, args.yes)?;
+
+    let instruction = into_instruction_data(bpf_loader_upgradeable::set_upgrade_authority(
+        &program_id,
+        &config.governance_id,
+        args.new_authority.as_ref(),
+    ));
+
+    let proposal = ProposalBuilder::new()
+        .name(args.name.unwrap_or(name))
+        .description(args.description.unwrap_or(description))
+        .mint_type(config.mint_type(args.mint_type))
+        .transaction(ProposalTransaction::new(0, vec![instruction]))
+        .build(&config)?;
+
+    proposal.send(&config)
+}
@@ -0,0 +1,331 @@
+use chrono::{TimeZone, Utc};
+use solana_program::clock::UnixTimestamp;
+use spl_governance::state::signatory_record::SignatoryRecordV2;
+
+use super::*;
+
+pub struct ShowProposalArgs {
+    pub global: GlobalArgs,
+    pub proposal_id: Option<Pubkey>,
+    pub latest: bool,
+    pub mint_type: Option<MintType>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionDetails {
+    pub label: String,
+    pub vote_weight: u64,
+    pub vote_result: String,
+    pub transactions_executed_count: u16,
+    pub transactions_count: u16,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timeline {
+    pub draft_at: UnixTimestamp,
+    pub signing_off_at: Option<UnixTimestamp>,
+    pub voting_at: Option<UnixTimestamp>,
+    pub voting_completed_at: Option<UnixTimestamp>,
+    pub executing_at: Option<UnixTimestamp>,
+    pub closed_at: Option<UnixTimestamp>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatoryDetails {
+    #[serde(with = "display_fromstr")]
+    pub signatory: Pubkey,
+    pub signed_off: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionDetails {
+    pub option_index: u8,
+    pub transaction_index: u16,
+    #[serde(with = "display_fromstr")]
+    pub address: Pubkey,
+    pub hold_up_time: u32,
+    pub execution_status: String,
+    pub executed_at: Option<UnixTimestamp>,
+    #[serde(serialize_with = "serialize_instructions")]
+    pub instructions: Vec<InstructionData>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalDetails {
+    #[serde(with = "display_fromstr")]
+    pub address: Pubkey,
+    pub name: String,
+    pub description: String,
+    pub state: String,
+    #[serde(with = "display_fromstr")]
+    pub governance: Pubkey,
+    #[serde(with = "display_fromstr")]
+    pub governing_token_mint: Pubkey,
+    pub vote_type: String,
+    pub options: Vec<OptionDetails>,
+    pub deny_vote_weight: Option<u64>,
+    pub max_vote_weight: Option<u64>,
+    pub timeline: Timeline,
+    pub signatories_count: u8,
+    pub signatories_signed_off_count: u8,
+    pub signatories: Vec<SignatoryDetails>,
+    pub transactions: Vec<TransactionDetails>,
+}
+
+impl fmt::Display for ProposalDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Proposal: {}", self.address)?;
+        writeln!(f, "Name: {}", self.name)?;
+        writeln!(f, "Description: {}", self.description)?;
+        writeln!(f, "State: {}", self.state)?;
+        writeln!(f, "Governance: {}", self.governance)?;
+        writeln!(f, "Governing Token Mint: {}", self.governing_token_mint)?;
+        writeln!(f, "Vote Type: {}", self.vote_type)?;
+
+        writeln!(f, "Options:")?;
+        for (i, option) in self.options.iter().enumerate() {
+            writeln!(
+                f,
+                "  [{i}] {}: {} yes, result {}, {}/{} transaction(s) executed",
+                option.label,
+                option.vote_weight,
+                option.vote_result,
+                option.transactions_executed_count,
+                option.transactions_count
+            )?;
+        }
+        if let Some(deny_vote_weight) = self.deny_vote_weight {
+            writeln!(f, "  No: {deny_vote_weight}")?;
+        }
+        if let Some(max_vote_weight) = self.max_vote_weight {
+            writeln!(f, "  Max vote weight: {max_vote_weight}")?;
+        }
+
+        writeln!(f, "Timeline:")?;
+        write_timestamp(f, "Draft", Some(self.timeline.draft_at))?;
+        write_timestamp(f, "Signing off", self.timeline.signing_off_at)?;
+        write_timestamp(f, "Voting", self.timeline.voting_at)?;
+        write_timestamp(f, "Voting completed", self.timeline.voting_completed_at)?;
+        write_timestamp(f, "Executing", self.timeline.executing_at)?;
+        write_timestamp(f, "Closed", self.timeline.closed_at)?;
+
+        writeln!(
+            f,
+            "Signatories: {}/{} signed off",
+            self.signatories_signed_off_count, self.signatories_count
+        )?;
+        for signatory in &self.signatories {
+            writeln!(
+                f,
+                "  {} {}",
+                signatory.signatory,
+                if signatory.signed_off {
+                    "signed off"
+                } else {
+                    "pending"
+                }
+            )?;
+        }
+
+        write!(f, "Transactions:")?;
+        for transaction in &self.transactions {
+            write!(
+                f,
+                "\n  [{}, {}] {}: hold up {}s, {}",
+                transaction.option_index,
+                transaction.transaction_index,
+                transaction.address,
+                transaction.hold_up_time,
+                transaction.execution_status
+            )?;
+            if let Some(executed_at) = transaction.executed_at {
+                write!(f, "\n    Executed at: {}", format_timestamp(executed_at))?;
+            }
+            for (i, instruction) in transaction.instructions.iter().enumerate() {
+                write!(f, "\n    Instruction #{i}:")?;
+                write!(f, "\n{}", format_instruction(instruction, 6))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn show_proposal(args: ShowProposalArgs) -> Result<ProposalDetails> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let proposal_id = resolve_proposal_id(
+        &config,
+        &config.mint_type(args.mint_type),
+        args.proposal_id,
+        args.latest,
+    )?;
+
+    debug!("Proposal ID: {proposal_id}");
+
+    let proposal: ProposalV2 = get_governance_state(&config.client, &proposal_id)?;
+
+    let mut transactions = vec![];
+    for (option_index, option) in proposal.options.iter().enumerate() {
+        let option_index = option_index as u8;
+
+        for (transaction_index, proposal_transaction_pubkey, proposal_transaction) in
+            get_option_transactions(
+                &config.client,
+                &proposal_id,
+                option_index,
+                option.transactions_next_index,
+            )?
+        {
+            transactions.push(TransactionDetails {
+                option_index,
+                transaction_index,
+                address: proposal_transaction_pubkey,
+                hold_up_time: proposal_transaction.hold_up_time,
+                execution_status: format!("{:?}", proposal_transaction.execution_status),
+                executed_at: proposal_transaction.executed_at,
+                instructions: proposal_transaction.instructions,
+            });
+        }
+    }
+
+    let signatories = get_signatory_records(&config.client, &proposal_id)?
+        .into_iter()
+        .map(|record| SignatoryDetails {
+            signatory: record.signatory,
+            signed_off: record.signed_off,
+        })
+        .collect();
+
+    Ok(ProposalDetails {
+        address: proposal_id,
+        name: proposal.name,
+        description: proposal.description_link,
+        state: format!("{:?}", proposal.state),
+        governance: proposal.governance,
+        governing_token_mint: proposal.governing_token_mint,
+        vote_type: format!("{:?}", proposal.vote_type),
+        options: proposal
+            .options
+            .into_iter()
+            .map(|option| OptionDetails {
+                label: option.label,
+                vote_weight: option.vote_weight,
+                vote_result: format!("{:?}", option.vote_result),
+                transactions_executed_count: option.transactions_executed_count,
+                transactions_count: option.transactions_count,
+            })
+            .collect(),
+        deny_vote_weight: proposal.deny_vote_weight,
+        max_vote_weight: proposal.max_vote_weight,
+        timeline: Timeline {
+            draft_at: proposal.draft_at,
+            signing_off_at: proposal.signing_off_at,
+            voting_at: proposal.voting_at,
+            voting_completed_at: proposal.voting_completed_at,
+            executing_at: proposal.executing_at,
+            closed_at: proposal.closed_at,
+        },
+        signatories_count: proposal.signatories_count,
+        signatories_signed_off_count: proposal.signatories_signed_off_count,
+        signatories,
+        transactions,
+    })
+}
+
+fn get_signatory_records(client: &RpcClient, proposal: &Pubkey) -> Result<Vec<SignatoryRecordV2>> {
+    let filters = vec![
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            0,
+            &[GovernanceAccountType::SignatoryRecordV2 as u8],
+        )),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(1, proposal.as_ref())),
+    ];
+
+    let results = client.get_program_accounts_with_config(
+        &GOVERNANCE_PROGRAM_ID,
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    results
+        .into_iter()
+        .map(|(_, account)| {
+            Ok(SignatoryRecordV2::deserialize(
+                &mut account.data.as_slice(),
+            )?)
+        })
+        .collect()
+}
+
+fn write_timestamp(
+    f: &mut fmt::Formatter<'_>,
+    label: &str,
+    timestamp: Option<UnixTimestamp>,
+) -> fmt::Result {
+    match timestamp {
+        Some(timestamp) => writeln!(f, "  {label}: {}", format_timestamp(timestamp)),
+        None => Ok(()),
+    }
+}
+
+fn format_timestamp(timestamp: UnixTimestamp) -> String {
+    match Utc.timestamp_opt(timestamp, 0).single() {
+        Some(datetime) => datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Serializes instructions with base58 pubkeys and data, as they are shown in instruction files.
+fn serialize_instructions<S>(
+    instructions: &[InstructionData],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct AccountOutput {
+        #[serde(with = "display_fromstr")]
+        pubkey: Pubkey,
+        is_signer: bool,
+        is_writable: bool,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct InstructionOutput {
+        #[serde(with = "display_fromstr")]
+        program_id: Pubkey,
+        accounts: Vec<AccountOutput>,
+        data: String,
+    }
+
+    serializer.collect_seq(instructions.iter().map(|instruction| {
+        InstructionOutput {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account| AccountOutput {
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: solana_sdk::bs58::encode(&instruction.data).into_string(),
+        }
+    }))
+}
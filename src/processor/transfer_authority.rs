@@ -0,0 +1,116 @@
+use spl_governance::{
+    instruction::create_program_governance, state::governance::get_program_governance_address,
+};
+
+use super::*;
+
+pub struct TransferAuthorityArgs {
+    pub global: GlobalArgs,
+    pub program_id: Option<Pubkey>,
+    pub create_governance: bool,
+    pub mint_type: Option<MintType>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorityTransferred {
+    #[serde(with = "display_fromstr")]
+    pub program_id: Pubkey,
+    /// Governance that is now the program's upgrade authority.
+    #[serde(with = "display_fromstr")]
+    pub governance: Pubkey,
+    /// Whether the governance was created for the program rather than an existing one.
+    pub created_governance: bool,
+    #[serde(with = "option_signature")]
+    pub signature: Option<Signature>,
+}
+
+impl fmt::Display for AuthorityTransferred {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Program: {}", self.program_id)?;
+        if self.created_governance {
+            writeln!(f, "Created program governance: {}", self.governance)?;
+        } else {
+            writeln!(f, "Upgrade authority: {}", self.governance)?;
+        }
+        write!(f, "Signature: {}", format_signature(&self.signature))
+    }
+}
+
+pub fn transfer_authority(args: TransferAuthorityArgs) -> Result<AuthorityTransferred> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let signer = config.keypair.pubkey();
+
+    let program_id = get_program_with_authority(&config, args.program_id, &signer)?.program_id;
+
+    // The governance's config is also the template of a new program governance.
+    let governance = get_governance_data(&config.client, &config.governance_id)?;
+    if governance.realm != config.realm_id {
+        return Err(anyhow!(
+            "Governance {} doesn't belong to realm {}",
+            config.governance_id,
+            config.realm_id
+        ));
+    }
+
+    let (instruction, new_authority) = if args.create_governance {
+        let program_governance =
+            get_program_governance_address(&GOVERNANCE_PROGRAM_ID, &config.realm_id, &program_id);
+        if config.client.get_account(&program_governance).is_ok() {
+            return Err(anyhow!(
+                "Program governance {program_governance} already exists"
+            ));
+        }
+
+        let realm = get_realm_data(&config.client, &config.realm_id)?;
+        let governing_token_mint = config
+            .mint_type(args.mint_type)
+            .governing_token_mint(&realm)?;
+        let token_owner_record = get_token_owner_record_address(
+            &GOVERNANCE_PROGRAM_ID,
+            &config.realm_id,
+            &governing_token_mint,
+            &signer,
+        );
+
+        let instruction = create_program_governance(
+            &GOVERNANCE_PROGRAM_ID,
+            &config.realm_id,
+            &program_id,
+            &signer,
+            &token_owner_record,
+            &signer,
+            &signer,
+            None,
+            governance.config,
+            true,
+        );
+
+        (instruction, program_governance)
+    } else {
+        let instruction = bpf_loader_upgradeable::set_upgrade_authority(
+            &program_id,
+            &signer,
+            Some(&config.governance_id),
+        );
+
+        (instruction, config.governance_id)
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&signer),
+        &[&config.keypair],
+        config.client.get_latest_blockhash()?,
+    );
+
+    let signature = send_transaction(&config, &tx)?;
+
+    Ok(AuthorityTransferred {
+        program_id,
+        governance: new_authority,
+        created_governance: args.create_governance,
+        signature,
+    })
+}
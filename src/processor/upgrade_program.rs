@@ -1,142 +1,502 @@
+use std::env;
+
+use crate::instruction::{read_upgrade_manifest, UpgradeSpec};
+
 use super::*;
 
 pub struct UpgradeProgramArgs {
-    pub keypair_path: Option<PathBuf>,
-    pub rpc_url: Option<String>,
-    pub source_buffer: Pubkey,
+    pub global: GlobalArgs,
+    pub program_id: Option<Pubkey>,
+    pub source_buffer: Option<Pubkey>,
+    pub manifest: Option<PathBuf>,
+    pub option_per_program: bool,
     pub spill_account: Option<Pubkey>,
     pub name: String,
-    pub description: String,
-    pub mint_type: MintType,
-    pub vote_type: VoteType,
+    pub description: Option<String>,
+    pub auto_description: bool,
+    pub mint_type: Option<MintType>,
+    pub choice_type: Option<ChoiceType>,
+    pub resume: Option<Pubkey>,
     pub options: Vec<String>,
+    pub no_extend: bool,
+    pub expected_hash: Option<String>,
+    pub git_commit: Option<String>,
+    pub link_template: Option<String>,
 }
 
-pub fn upgrade_program(args: UpgradeProgramArgs) -> Result<()> {
-    let config = config::CliConfig::new(args.keypair_path, args.rpc_url)?;
+pub fn upgrade_program(args: UpgradeProgramArgs) -> Result<ProposalCreated> {
+    let config = config::CliConfig::new(&args.global)?;
 
-    let realm = get_realm_data(&config.client, &config.realm_id)?;
-    let governance = get_governance_data(&config.client, &config.governance_id)?;
+    let upgrades = match args.manifest {
+        Some(manifest) => read_upgrade_manifest(&manifest)?,
+        None => vec![UpgradeSpec {
+            program_id: resolve_program_id(&config, args.program_id)?,
+            buffer: args
+                .source_buffer
+                .ok_or_else(|| anyhow!("Either --source-buffer or --manifest must be provided"))?,
+            spill_account: None,
+            expected_hash: args.expected_hash,
+            label: None,
+        }],
+    };
+
+    // Catch what doesn't need the network first, before every buffer is fetched and checked.
+    for (i, spec) in upgrades.iter().enumerate() {
+        if upgrades[..i]
+            .iter()
+            .any(|other| other.program_id == spec.program_id)
+        {
+            return Err(anyhow!(
+                "Program {} is listed more than once",
+                spec.program_id
+            ));
+        }
+    }
 
-    let governing_token_mint = match args.mint_type {
-        MintType::Member => realm.community_mint,
-        MintType::Council => realm
-            .config
-            .council_mint
-            .ok_or_else(|| anyhow!("Council mint not found"))?,
+    // Either every upgrade is approved together under the first option, or each program gets an
+    // option of its own so they can pass independently.
+    let options = if !args.option_per_program || !args.options.is_empty() {
+        if args.option_per_program && args.options.len() != upgrades.len() {
+            return Err(anyhow!(
+                "{} option(s) given for {} program(s)",
+                args.options.len(),
+                upgrades.len()
+            ));
+        }
+        args.options
+    } else {
+        upgrades
+            .iter()
+            .map(|spec| {
+                spec.label
+                    .clone()
+                    .unwrap_or_else(|| format!("Upgrade {}", spec.program_id))
+            })
+            .collect()
     };
 
-    debug!("Governing Token Mint: {governing_token_mint}");
+    let git_commit = args.git_commit.or_else(|| env::var("GIT_COMMIT").ok());
+    let link_template = args.link_template.or_else(|| config.link_template.clone());
 
-    let proposal_index: u32 = governance.proposals_count;
+    let description_style = if args.auto_description {
+        let program_ids: Vec<Pubkey> = upgrades.iter().map(|spec| spec.program_id).collect();
+        Some(description_style(
+            &program_ids,
+            git_commit.as_deref(),
+            link_template.as_deref(),
+            max_description_len(&args.name, &options, args.choice_type.as_ref()),
+        )?)
+    } else {
+        None
+    };
 
-    debug!("Proposal index: {proposal_index}");
+    let spill_account = args
+        .spill_account
+        .unwrap_or_else(|| config.keypair.pubkey());
 
-    let proposal_owner_record = get_token_owner_record_address(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.realm_id,
-        &governing_token_mint,
-        &config.keypair.pubkey(),
-    );
+    let mut prepared: Vec<PreparedUpgrade> = vec![];
+    for spec in upgrades {
+        prepared.push(prepare_upgrade(
+            &config,
+            spec,
+            spill_account,
+            args.no_extend,
+        )?);
+    }
 
-    debug!("Proposal Owner Record: {proposal_owner_record}");
-
-    let create_ix = create_proposal(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.governance_id,
-        &proposal_owner_record,
-        &config.keypair.pubkey(),
-        &config.keypair.pubkey(),
-        None,
-        &config.realm_id,
-        args.name,
-        args.description,
-        &governing_token_mint,
-        args.vote_type,
-        args.options,
-        true,
-        proposal_index,
-    );
+    let description = match description_style {
+        Some(style) => upgrade_description(
+            &prepared,
+            style,
+            git_commit.as_deref(),
+            link_template.as_deref(),
+        ),
+        None => args.description.ok_or_else(|| {
+            anyhow!("Either --description or --auto-description must be provided")
+        })?,
+    };
 
-    let proposal_address = get_proposal_address(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.governance_id,
-        &governing_token_mint,
-        &proposal_index.to_le_bytes(),
-    );
+    debug!("Description: {description}");
+
+    // Transactions execute in index order within an option, so ProgramData is extended before
+    // the upgrade that needs the room.
+    let mut transactions = vec![];
+    for (i, upgrade) in prepared.into_iter().enumerate() {
+        let option_index = if args.option_per_program { i as u8 } else { 0 };
+        for instruction in upgrade.extend {
+            transactions.push(ProposalTransaction::new(option_index, vec![instruction]));
+        }
+        transactions.push(ProposalTransaction::new(
+            option_index,
+            vec![upgrade.upgrade],
+        ));
+    }
 
-    debug!("Proposal Address: {proposal_address}");
+    let proposal = ProposalBuilder::new()
+        .name(args.name)
+        .description(description)
+        .mint_type(config.mint_type(args.mint_type))
+        .choice_type(args.choice_type)
+        .resume(args.resume)
+        .options(options)
+        .transactions(transactions)
+        .build(&config)?;
 
-    let token_owner_record = get_token_owner_record_address(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.realm_id,
-        &governing_token_mint,
-        &config.keypair.pubkey(),
-    );
+    proposal.send(&config)
+}
 
-    debug!("Token Owner Record: {token_owner_record}");
+/// A program upgrade that passed the pre-flight checks, with the instructions performing it.
+struct PreparedUpgrade {
+    program: UpgradeableProgram,
+    buffer: UpgradeableBuffer,
+    /// Hash of the executable in the buffer, taken from the same fetch as the checks.
+    buffer_hash: String,
+    /// ExtendProgram instructions making room for the new program, if it doesn't fit.
+    extend: Vec<InstructionData>,
+    upgrade: InstructionData,
+}
 
-    let add_signatory_ix = add_signatory(
-        &GOVERNANCE_PROGRAM_ID,
-        &proposal_address,
-        &token_owner_record,
-        &config.keypair.pubkey(),
-        &config.keypair.pubkey(),
-        &config.keypair.pubkey(),
-    );
+fn prepare_upgrade(
+    config: &config::CliConfig,
+    spec: UpgradeSpec,
+    spill_account: Pubkey,
+    no_extend: bool,
+) -> Result<PreparedUpgrade> {
+    let program = get_governed_program(config, Some(spec.program_id))?;
 
-    // Empirically determined from existing proposals. Not sure the significance of these yet.
-    let option_index = 0;
-    let index = 0;
-    let hold_up_time = 0;
+    debug!("Program: {:#?}", program);
 
-    let program_upgrade_instruction = create_upgrade_program_instruction(
-        args.source_buffer,
-        args.spill_account
-            .unwrap_or_else(|| config.keypair.pubkey()),
+    let buffer = get_upgradeable_buffer(&config.client, &spec.buffer)?;
+
+    debug!("Buffer: {:#?}", buffer);
+
+    if buffer.authority != config.governance_id {
+        return Err(anyhow!(
+            "Buffer {} authority is {}, not the governance {}. Transfer it with `solana program set-buffer-authority {} --new-buffer-authority {}`",
+            buffer.address,
+            buffer.authority,
+            config.governance_id,
+            buffer.address,
+            config.governance_id
+        ));
+    }
+
+    let buffer_hash = buffer
+        .hash
+        .clone()
+        .expect("get_upgradeable_buffer fetches the whole buffer");
+
+    if let Some(expected_hash) = spec.expected_hash {
+        if !buffer_hash.eq_ignore_ascii_case(&expected_hash) {
+            return Err(anyhow!(
+                "Buffer {} hash is {buffer_hash}, expected {expected_hash}",
+                buffer.address
+            ));
+        }
+    }
+
+    let program_len = buffer
+        .data_len
+        .saturating_sub(UpgradeableLoaderState::size_of_buffer_metadata());
+    if program_len == 0 {
+        return Err(anyhow!("Buffer {} is empty", buffer.address));
+    }
+
+    let mut extend = vec![];
+
+    let required_len = UpgradeableLoaderState::size_of_programdata_metadata() + program_len;
+    if required_len > program.data_len {
+        let additional_bytes = required_len - program.data_len;
+
+        if no_extend {
+            return Err(anyhow!(
+                "ProgramData {} is {additional_bytes} bytes too small for buffer {}",
+                program.program_data,
+                buffer.address
+            ));
+        }
+
+        eprintln!(
+            "ProgramData {} is {additional_bytes} bytes too small for buffer {}, extending it first",
+            program.program_data, buffer.address
+        );
+        extend = create_extend_program_instructions(config, &program, additional_bytes)?;
+    }
+
+    let upgrade = create_upgrade_program_instruction(
+        spec.program_id,
+        program.program_data,
+        spec.buffer,
+        spec.spill_account.unwrap_or(spill_account),
         config.governance_id,
-    )?;
-
-    let insert_ix = insert_transaction(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.governance_id,
-        &proposal_address,
-        &token_owner_record,
-        &config.keypair.pubkey(),
-        &config.keypair.pubkey(),
-        option_index,
-        index,
-        hold_up_time,
-        vec![program_upgrade_instruction],
     );
 
-    let sign_off_ix = sign_off_proposal(
-        &GOVERNANCE_PROGRAM_ID,
-        &config.realm_id,
-        &config.governance_id,
-        &proposal_address,
-        &config.keypair.pubkey(),
-        None,
-    );
+    Ok(PreparedUpgrade {
+        program,
+        buffer,
+        buffer_hash,
+        extend,
+        upgrade,
+    })
+}
 
-    let tx = solana_sdk::transaction::Transaction::new_signed_with_payer(
-        &[create_ix, add_signatory_ix, insert_ix, sign_off_ix],
-        Some(&config.keypair.pubkey()),
-        &[&config.keypair],
-        config.client.get_latest_blockhash()?,
-    );
+/// What the generated description says about a single upgrade.
+struct DescribedUpgrade {
+    program_id: Pubkey,
+    current_hash: String,
+    new_hash: String,
+    program_len: usize,
+    program_data_len: usize,
+}
 
-    match config.client.send_and_confirm_transaction_with_spinner(&tx) {
-        Ok(signature) => {
-            println!("Upgrade program proposal signature: {}", signature);
-            Ok(())
+/// How much the generated description says about each program, from the most detailed to what
+/// still fits when many programs are upgraded together.
+#[derive(Clone, Copy, Debug)]
+enum DescriptionStyle {
+    /// Both hashes and sizes of each program.
+    Detailed,
+    /// One line per program with its new hash and size.
+    Compact,
+    /// Only the number of programs, leaving the details to the commit and link.
+    Summary,
+}
+
+/// Picks the most detailed description that fits in the proposal, before anything is fetched.
+/// Hashes have a fixed length, so measuring with the largest possible sizes is an upper bound.
+fn description_style(
+    program_ids: &[Pubkey],
+    git_commit: Option<&str>,
+    link_template: Option<&str>,
+    max_len: usize,
+) -> Result<DescriptionStyle> {
+    if let Some(link_template) = link_template {
+        if link_template.contains("{commit}") && git_commit.is_none() {
+            return Err(anyhow!(
+                "The link template needs a commit, use --git-commit or GIT_COMMIT"
+            ));
+        }
+    }
+
+    let placeholders: Vec<DescribedUpgrade> = program_ids
+        .iter()
+        .map(|program_id| DescribedUpgrade {
+            program_id: *program_id,
+            current_hash: "0".repeat(64),
+            new_hash: "0".repeat(64),
+            program_len: MAX_PERMITTED_DATA_LENGTH as usize,
+            program_data_len: MAX_PERMITTED_DATA_LENGTH as usize,
+        })
+        .collect();
+
+    [
+        DescriptionStyle::Detailed,
+        DescriptionStyle::Compact,
+        DescriptionStyle::Summary,
+    ]
+    .into_iter()
+    .find(|style| {
+        format_upgrade_description(&placeholders, *style, git_commit, link_template).len()
+            <= max_len
+    })
+    .ok_or_else(|| {
+        anyhow!(
+            "A generated description for {} program(s) doesn't fit in the proposal, pass --description instead",
+            program_ids.len()
+        )
+    })
+}
+
+/// Describes what the upgrade changes so every upgrade proposal can be audited the same way.
+fn upgrade_description(
+    upgrades: &[PreparedUpgrade],
+    style: DescriptionStyle,
+    git_commit: Option<&str>,
+    link_template: Option<&str>,
+) -> String {
+    let described: Vec<DescribedUpgrade> = upgrades
+        .iter()
+        .map(|upgrade| DescribedUpgrade {
+            program_id: upgrade.program.program_id,
+            current_hash: upgrade.program.hash.clone(),
+            new_hash: upgrade.buffer_hash.clone(),
+            program_len: upgrade.buffer.data_len
+                - UpgradeableLoaderState::size_of_buffer_metadata(),
+            program_data_len: upgrade.program.data_len
+                - UpgradeableLoaderState::size_of_programdata_metadata(),
+        })
+        .collect();
+
+    format_upgrade_description(&described, style, git_commit, link_template)
+}
+
+fn format_upgrade_description(
+    upgrades: &[DescribedUpgrade],
+    style: DescriptionStyle,
+    git_commit: Option<&str>,
+    link_template: Option<&str>,
+) -> String {
+    let link = |program_id: &Pubkey| {
+        link_template.map(|link_template| {
+            link_template
+                .replace("{commit}", git_commit.unwrap_or_default())
+                .replace("{program_id}", &program_id.to_string())
+        })
+    };
+    // A link without the program id is the same for every program, so it's only listed once.
+    let link_per_program =
+        link_template.is_some_and(|link_template| link_template.contains("{program_id}"));
+
+    let mut lines = vec![];
+    match style {
+        DescriptionStyle::Detailed => {
+            for upgrade in upgrades {
+                lines.push(format!("Upgrade program {}", upgrade.program_id));
+                lines.push(format!("Current hash: {}", upgrade.current_hash));
+                lines.push(format!("New hash: {}", upgrade.new_hash));
+                lines.push(format!(
+                    "Size: {} bytes, ProgramData holds {} bytes",
+                    upgrade.program_len, upgrade.program_data_len
+                ));
+                if link_per_program {
+                    lines.extend(link(&upgrade.program_id));
+                }
+            }
+        }
+        DescriptionStyle::Compact => {
+            for upgrade in upgrades {
+                lines.push(format!(
+                    "Upgrade {} to {}, {} bytes",
+                    upgrade.program_id, upgrade.new_hash, upgrade.program_len
+                ));
+                if link_per_program {
+                    lines.extend(link(&upgrade.program_id));
+                }
+            }
+        }
+        DescriptionStyle::Summary => {
+            lines.push(format!("Upgrade {} programs", upgrades.len()));
         }
-        Err(error) => {
-            debug!(
-                "Error creating proposal: {:?}",
-                error.get_transaction_error()
-            );
-            Err(error.into())
+    }
+
+    if let Some(git_commit) = git_commit {
+        lines.push(format!("Commit: {git_commit}"));
+    }
+
+    if !link_per_program {
+        lines.extend(link(&upgrades[0].program_id));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn described(program_id: Pubkey) -> DescribedUpgrade {
+        DescribedUpgrade {
+            program_id,
+            current_hash: "a".repeat(64),
+            new_hash: "b".repeat(64),
+            program_len: 1000,
+            program_data_len: 2000,
         }
     }
+
+    #[test]
+    fn detailed_lists_both_hashes_and_sizes() {
+        let program_id = Pubkey::new_unique();
+        let description = format_upgrade_description(
+            &[described(program_id)],
+            DescriptionStyle::Detailed,
+            Some("abc123"),
+            Some("https://example.com/{commit}/{program_id}"),
+        );
+
+        assert_eq!(
+            description,
+            format!(
+                "Upgrade program {program_id}\nCurrent hash: {}\nNew hash: {}\n\
+                 Size: 1000 bytes, ProgramData holds 2000 bytes\n\
+                 https://example.com/abc123/{program_id}\nCommit: abc123",
+                "a".repeat(64),
+                "b".repeat(64)
+            )
+        );
+    }
+
+    #[test]
+    fn compact_has_a_line_per_program() {
+        let program_ids = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let description = format_upgrade_description(
+            &program_ids.map(described),
+            DescriptionStyle::Compact,
+            None,
+            Some("https://example.com/release"),
+        );
+
+        assert_eq!(
+            description,
+            format!(
+                "Upgrade {} to {hash}, 1000 bytes\nUpgrade {} to {hash}, 1000 bytes\n\
+                 https://example.com/release",
+                program_ids[0],
+                program_ids[1],
+                hash = "b".repeat(64)
+            )
+        );
+    }
+
+    #[test]
+    fn summary_only_counts_the_programs() {
+        let program_ids = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let description = format_upgrade_description(
+            &program_ids.map(described),
+            DescriptionStyle::Summary,
+            Some("abc123"),
+            Some("https://example.com/{commit}/{program_id}"),
+        );
+
+        assert_eq!(description, "Upgrade 2 programs\nCommit: abc123");
+    }
+
+    #[test]
+    fn description_style_falls_back_to_what_fits() {
+        let program_ids = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let len = |style| {
+            let placeholders: Vec<DescribedUpgrade> = program_ids
+                .iter()
+                .map(|program_id| DescribedUpgrade {
+                    program_id: *program_id,
+                    current_hash: "0".repeat(64),
+                    new_hash: "0".repeat(64),
+                    program_len: MAX_PERMITTED_DATA_LENGTH as usize,
+                    program_data_len: MAX_PERMITTED_DATA_LENGTH as usize,
+                })
+                .collect();
+            format_upgrade_description(&placeholders, style, Some("abc123"), None).len()
+        };
+        let style = |max_len| description_style(&program_ids, Some("abc123"), None, max_len);
+
+        let detailed = len(DescriptionStyle::Detailed);
+        let compact = len(DescriptionStyle::Compact);
+        let summary = len(DescriptionStyle::Summary);
+
+        assert!(matches!(style(detailed), Ok(DescriptionStyle::Detailed)));
+        assert!(matches!(style(detailed - 1), Ok(DescriptionStyle::Compact)));
+        assert!(matches!(style(compact - 1), Ok(DescriptionStyle::Summary)));
+        assert!(style(summary - 1).is_err());
+    }
+
+    #[test]
+    fn description_style_needs_a_commit_for_the_link() {
+        let result = description_style(
+            &[Pubkey::new_unique()],
+            None,
+            Some("https://example.com/{commit}"),
+            usize::MAX,
+        );
+
+        assert!(result.is_err());
+    }
 }
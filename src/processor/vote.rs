@@ -1,44 +1,26 @@
 use super::*;
 
 pub struct VoteArgs {
-    pub keypair_path: Option<PathBuf>,
-    pub rpc_url: Option<String>,
+    pub global: GlobalArgs,
     pub proposal_id: Option<Pubkey>,
     pub latest: bool,
-    pub mint_type: MintType,
+    pub mint_type: Option<MintType>,
     pub vote_choice: Vote,
+    pub approve_options: Vec<u8>,
 }
 
-pub fn vote(args: VoteArgs) -> Result<()> {
-    let config = config::CliConfig::new(args.keypair_path, args.rpc_url)?;
+pub fn vote(args: VoteArgs) -> Result<SignatureOutput> {
+    let config = config::CliConfig::new(&args.global)?;
 
     let realm: RealmV2 = get_governance_state(&config.client, &config.realm_id)?;
 
-    let governing_token_mint = match args.mint_type {
-        MintType::Member => realm.community_mint,
-        MintType::Council => realm
-            .config
-            .council_mint
-            .ok_or_else(|| anyhow!("Council mint not found"))?,
-    };
+    let mint_type = config.mint_type(args.mint_type);
+
+    let governing_token_mint = mint_type.governing_token_mint(&realm)?;
 
     debug!("Governing Token Mint: {governing_token_mint}");
 
-    let proposal_id = if args.latest {
-        let governance: GovernanceV2 = get_governance_state(&config.client, &config.governance_id)?;
-        let proposal_index = governance.proposals_count - 1;
-
-        get_proposal_address(
-            &GOVERNANCE_PROGRAM_ID,
-            &config.governance_id,
-            &governing_token_mint,
-            &proposal_index.to_le_bytes(),
-        )
-    } else if let Some(proposal_id) = args.proposal_id {
-        proposal_id
-    } else {
-        return Err(anyhow!("Either --latest or --proposal-id must be provided"));
-    };
+    let proposal_id = resolve_proposal_id(&config, &mint_type, args.proposal_id, args.latest)?;
 
     debug!("Proposal ID: {proposal_id}");
 
@@ -68,7 +50,11 @@ pub fn vote(args: VoteArgs) -> Result<()> {
 
     debug!("Voter Token Owner Record: {voter_token_owner_record}");
 
-    let vote: SplVote = args.vote_choice.into();
+    let vote: SplVote = args.vote_choice.into_spl_vote(
+        &proposal.vote_type,
+        proposal.options.len(),
+        &args.approve_options,
+    )?;
 
     let ix = cast_vote(
         &GOVERNANCE_PROGRAM_ID,
@@ -92,9 +78,7 @@ pub fn vote(args: VoteArgs) -> Result<()> {
         config.client.get_latest_blockhash()?,
     );
 
-    config
-        .client
-        .send_and_confirm_transaction_with_spinner(&tx)?;
+    let signature = send_transaction(&config, &tx)?;
 
-    Ok(())
+    Ok(SignatureOutput { signature })
 }
@@ -0,0 +1,311 @@
+use std::{fs, thread, time::Duration};
+
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::signature::Keypair;
+
+use super::*;
+
+pub struct WriteBufferArgs {
+    pub global: GlobalArgs,
+    pub program_path: PathBuf,
+    pub resume: Option<Pubkey>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferWritten {
+    #[serde(with = "display_fromstr")]
+    pub buffer: Pubkey,
+    #[serde(with = "display_fromstr")]
+    pub authority: Pubkey,
+    pub program_len: usize,
+}
+
+impl fmt::Display for BufferWritten {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Buffer: {}", self.buffer)?;
+        writeln!(f, "Authority: {}", self.authority)?;
+        write!(f, "Program size: {} bytes", self.program_len)
+    }
+}
+
+/// Number of rounds of sending the chunks that are still missing before giving up.
+const MAX_WRITE_ATTEMPTS: usize = 5;
+/// Maximum number of signatures `getSignatureStatuses` accepts per request.
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+pub fn write_buffer(args: WriteBufferArgs) -> Result<BufferWritten> {
+    let config = config::CliConfig::new(&args.global)?;
+
+    let program = fs::read(&args.program_path)
+        .map_err(|e| anyhow!("Unable to read {}: {e}", args.program_path.display()))?;
+    if !program.starts_with(b"\x7fELF") {
+        return Err(anyhow!(
+            "{} is not an ELF file",
+            args.program_path.display()
+        ));
+    }
+
+    let authority = config.keypair.pubkey();
+
+    let buffer = match args.resume {
+        Some(buffer) => {
+            let existing = get_upgradeable_buffer(&config.client, &buffer)?;
+            if existing.data_len != UpgradeableLoaderState::size_of_buffer(program.len()) {
+                return Err(anyhow!(
+                    "Buffer {buffer} doesn't have the size of {}",
+                    args.program_path.display()
+                ));
+            }
+            if existing.authority == config.governance_id {
+                // Already handed off, so it can only be checked.
+                verify_buffer(&config, &buffer, &program)?;
+                return Ok(BufferWritten {
+                    buffer,
+                    authority: config.governance_id,
+                    program_len: program.len(),
+                });
+            }
+            if existing.authority != authority {
+                return Err(anyhow!(
+                    "Buffer {buffer} authority is {}, not {authority}",
+                    existing.authority
+                ));
+            }
+            buffer
+        }
+        None => create_buffer(&config, program.len())?,
+    };
+
+    if config.dry_run {
+        let chunks = program.len().div_ceil(max_chunk_size(&buffer, &authority));
+        eprintln!("Dry run, {chunks} write transaction(s) and the authority transfer not sent");
+        return Ok(BufferWritten {
+            buffer,
+            authority,
+            program_len: program.len(),
+        });
+    }
+
+    write_chunks(&config, &buffer, &program).inspect_err(|_| {
+        eprintln!(
+            "Buffer {buffer} was partially written, re-run with --resume {buffer} to continue"
+        );
+    })?;
+
+    verify_buffer(&config, &buffer, &program)?;
+
+    let ix =
+        bpf_loader_upgradeable::set_buffer_authority(&buffer, &authority, &config.governance_id);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&authority),
+        &[&config.keypair],
+        config.client.get_latest_blockhash()?,
+    );
+
+    let signature = send_transaction(&config, &tx)?;
+
+    debug!(
+        "Buffer authority transferred: {}",
+        format_signature(&signature)
+    );
+
+    Ok(BufferWritten {
+        buffer,
+        authority: config.governance_id,
+        program_len: program.len(),
+    })
+}
+
+fn create_buffer(config: &config::CliConfig, program_len: usize) -> Result<Pubkey> {
+    let buffer = Keypair::new();
+    let authority = config.keypair.pubkey();
+
+    let lamports = config.client.get_minimum_balance_for_rent_exemption(
+        UpgradeableLoaderState::size_of_buffer(program_len),
+    )?;
+
+    let instructions = bpf_loader_upgradeable::create_buffer(
+        &authority,
+        &buffer.pubkey(),
+        &authority,
+        lamports,
+        program_len,
+    )?;
+
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&authority),
+        &[&config.keypair, &buffer],
+        config.client.get_latest_blockhash()?,
+    );
+
+    let signature = send_transaction(config, &tx)?;
+
+    match signature {
+        Some(signature) => eprintln!("Created buffer {}: {signature}", buffer.pubkey()),
+        None => eprintln!("Dry run, buffer {} not created", buffer.pubkey()),
+    }
+
+    Ok(buffer.pubkey())
+}
+
+/// Sends every chunk that differs from the buffer's current contents without waiting for each
+/// one, then resends the ones that didn't land. An interrupted upload only redoes what's missing.
+fn write_chunks(config: &config::CliConfig, buffer: &Pubkey, program: &[u8]) -> Result<()> {
+    let authority = config.keypair.pubkey();
+    let chunk_size = max_chunk_size(buffer, &authority);
+
+    for attempt in 0..=MAX_WRITE_ATTEMPTS {
+        let data = config.client.get_account_data(buffer)?;
+        let written = &data[UpgradeableLoaderState::size_of_buffer_metadata()..];
+
+        let pending: Vec<usize> = program
+            .chunks(chunk_size)
+            .zip(written.chunks(chunk_size))
+            .enumerate()
+            .filter(|(_, (expected, actual))| expected != actual)
+            .map(|(i, _)| i)
+            .collect();
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+        if attempt == MAX_WRITE_ATTEMPTS {
+            break;
+        }
+
+        eprintln!(
+            "Writing {} chunk(s) to buffer {buffer} (attempt {}/{MAX_WRITE_ATTEMPTS})",
+            pending.len(),
+            attempt + 1
+        );
+
+        let blockhash = config.client.get_latest_blockhash()?;
+
+        let mut signatures = vec![];
+        for i in pending {
+            let offset = i * chunk_size;
+            let bytes = program[offset..(offset + chunk_size).min(program.len())].to_vec();
+
+            let tx = Transaction::new_signed_with_payer(
+                &[bpf_loader_upgradeable::write(
+                    buffer,
+                    &authority,
+                    offset as u32,
+                    bytes,
+                )],
+                Some(&authority),
+                &[&config.keypair],
+                blockhash,
+            );
+
+            match config.client.send_transaction_with_config(
+                &tx,
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    ..RpcSendTransactionConfig::default()
+                },
+            ) {
+                Ok(signature) => signatures.push(signature),
+                Err(error) => debug!("Error sending chunk {i}: {error}"),
+            }
+        }
+
+        wait_for_signatures(config, &signatures, &blockhash)?;
+    }
+
+    Err(anyhow!(
+        "Buffer {buffer} is still incomplete after {MAX_WRITE_ATTEMPTS} attempts"
+    ))
+}
+
+/// Waits until every transaction is confirmed or failed, or their blockhash expires.
+fn wait_for_signatures(
+    config: &config::CliConfig,
+    signatures: &[Signature],
+    blockhash: &solana_sdk::hash::Hash,
+) -> Result<()> {
+    let mut pending = signatures.to_vec();
+
+    while !pending.is_empty() {
+        thread::sleep(Duration::from_millis(500));
+
+        let mut still_pending = vec![];
+        for chunk in pending.chunks(MAX_SIGNATURE_STATUSES) {
+            let statuses = config.client.get_signature_statuses(chunk)?.value;
+            for (signature, status) in chunk.iter().zip(statuses) {
+                match status {
+                    Some(status) if status.satisfies_commitment(config.client.commitment()) => {}
+                    _ => still_pending.push(*signature),
+                }
+            }
+        }
+        pending = still_pending;
+
+        if !config
+            .client
+            .is_blockhash_valid(blockhash, config.client.commitment())?
+        {
+            debug!("Blockhash expired with {} write(s) pending", pending.len());
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn verify_buffer(config: &config::CliConfig, buffer: &Pubkey, program: &[u8]) -> Result<()> {
+    let data = config.client.get_account_data(buffer)?;
+    if &data[UpgradeableLoaderState::size_of_buffer_metadata()..] != program {
+        return Err(anyhow!("Buffer {buffer} contents don't match the program"));
+    }
+    Ok(())
+}
+
+/// Largest chunk of program data that fits in a single write transaction.
+fn max_chunk_size(buffer: &Pubkey, authority: &Pubkey) -> usize {
+    // Past 127 bytes the instruction data length prefix stops growing, so the size is linear
+    // in the chunk from there.
+    const MEASURED_CHUNK: usize = 128;
+    let size = transaction_size(
+        &[bpf_loader_upgradeable::write(
+            buffer,
+            authority,
+            0,
+            vec![0; MEASURED_CHUNK],
+        )],
+        authority,
+    );
+
+    PACKET_DATA_SIZE - size + MEASURED_CHUNK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_chunk_fills_the_transaction() {
+        let buffer = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let chunk_size = max_chunk_size(&buffer, &authority);
+
+        let size = |len| {
+            transaction_size(
+                &[bpf_loader_upgradeable::write(
+                    &buffer,
+                    &authority,
+                    0,
+                    vec![0; len],
+                )],
+                &authority,
+            )
+        };
+
+        assert_eq!(size(chunk_size), PACKET_DATA_SIZE);
+        assert!(size(chunk_size + 1) > PACKET_DATA_SIZE);
+    }
+}